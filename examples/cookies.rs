@@ -1,4 +1,3 @@
-use async_std::task;
 use cookie::Cookie;
 use tide::{Request, Response};
 
@@ -20,15 +19,14 @@ async fn remove_cookie(_req: Request<()>) -> Response {
     res
 }
 
-fn main() -> Result<(), std::io::Error> {
-    task::block_on(async {
-        let mut app = tide::new();
+#[tokio::main]
+async fn main() -> Result<(), std::io::Error> {
+    let mut app = tide::new();
 
-        app.at("/").get(retrieve_cookie);
-        app.at("/set").get(set_cookie);
-        app.at("/remove").get(remove_cookie);
-        app.listen("127.0.0.1:8080").await?;
+    app.at("/").get(retrieve_cookie);
+    app.at("/set").get(set_cookie);
+    app.at("/remove").get(remove_cookie);
+    app.listen("127.0.0.1:8080").await?;
 
-        Ok(())
-    })
+    Ok(())
 }
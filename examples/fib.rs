@@ -1,4 +1,3 @@
-use async_std::task;
 use tide::Request;
 
 fn fib(n: usize) -> usize {
@@ -28,11 +27,10 @@ async fn fibsum(req: Request<()>) -> String {
 // $ curl "http://localhost:8080/fib/42"
 // The fib of 42 is 267914296.
 // It was computed in 2 secs.
-fn main() -> Result<(), std::io::Error> {
-    task::block_on(async {
-        let mut app = tide::new();
-        app.at("/fib/:n").get(fibsum);
-        app.listen("0.0.0.0:8080").await?;
-        Ok(())
-    })
+#[tokio::main]
+async fn main() -> Result<(), std::io::Error> {
+    let mut app = tide::new();
+    app.at("/fib/:n").get(fibsum);
+    app.listen("0.0.0.0:8080").await?;
+    Ok(())
 }
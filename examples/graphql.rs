@@ -1,4 +1,3 @@
-use async_std::task;
 use juniper::RootNode;
 use std::sync::RwLock;
 use tide::{Request, Response, Server};
@@ -29,10 +28,10 @@ struct NewUser {
 }
 
 impl NewUser {
-    fn to_internal(self) -> User {
+    fn into_internal(self) -> User {
         User {
             id: None,
-            first_name: self.first_name.to_owned(),
+            first_name: self.first_name,
         }
     }
 }
@@ -49,7 +48,7 @@ impl QueryRoot {
     #[graphql(description = "Get all Users")]
     fn users(context: &State) -> Vec<User> {
         let users = context.users.read().unwrap();
-        users.iter().map(|u| u.clone()).collect()
+        users.iter().cloned().collect()
     }
 }
 
@@ -60,7 +59,7 @@ impl MutationRoot {
     #[graphql(description = "Add new user")]
     fn add_user(context: &State, user: NewUser) -> User {
         let mut users = context.users.write().unwrap();
-        let mut user = user.to_internal();
+        let mut user = user.into_internal();
         user.id = Some((users.len() + 1) as u16);
         users.push(user.clone());
         user
@@ -93,15 +92,14 @@ async fn handle_graphiql(_: Request<State>) -> Response {
         .set_header("content-type", "text/html;charset=utf-8")
 }
 
-fn main() -> std::io::Result<()> {
-    task::block_on(async {
-        let mut app = Server::with_state(State {
-            users: RwLock::new(Vec::new()),
-        });
-        app.at("/").get(tide::redirect("/graphiql"));
-        app.at("/graphql").post(handle_graphql);
-        app.at("/graphiql").get(handle_graphiql);
-        app.listen("0.0.0.0:8080").await?;
-        Ok(())
-    })
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let mut app = Server::with_state(State {
+        users: RwLock::new(Vec::new()),
+    });
+    app.at("/").get(tide::redirect("/graphiql"));
+    app.at("/graphql").post(handle_graphql);
+    app.at("/graphiql").get(handle_graphiql);
+    app.listen("0.0.0.0:8080").await?;
+    Ok(())
 }
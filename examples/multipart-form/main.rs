@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use tide::{Request, Response};
+
+#[derive(Deserialize)]
+struct Form {
+    key1: Option<String>,
+    key2: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    key1: Option<String>,
+    key2: Option<String>,
+    file: Option<String>,
+}
+
+async fn upload_file(mut req: Request<()>) -> Result<Response, tide::Error> {
+    let form = req
+        .body_multipart()?
+        .part_limit(1024 * 1024)
+        .into_form::<Form>()
+        .await?;
+
+    let file = match form.file("file") {
+        Some(file) => Some(tokio::fs::read_to_string(file.path()).await?),
+        None => None,
+    };
+    let message = Message {
+        key1: form.fields.key1,
+        key2: form.fields.key2,
+        file,
+    };
+    Ok(Response::new(200).body_json(&message)?)
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let mut app = tide::new();
+    app.at("/upload_file").post(upload_file);
+    app.listen("127.0.0.1:8000").await?;
+    Ok(())
+}
+
+// Test with:
+// curl -X POST http://localhost:8000/upload_file -F file=@examples/multipart-form/test.txt
+// curl -X POST http://localhost:8000/upload_file -F key1=v1 -F key2=v2
@@ -1,41 +1,466 @@
 //! Tide error types.
 use hyper::StatusCode;
 
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+
 use crate::response::{IntoResponse, Response};
-use hyper::Body;
+use crate::Problem;
 
 /// A specialized Result type for Tide.
 pub type Result<T = Response> = std::result::Result<T, Error>;
 
-/// A generic error.
-#[derive(Debug, derive_more::From)]
-pub enum Error {
-    Hyper(hyper::Error),
-    Response(Response),
-    IO(std::io::Error)
+/// A boxed error, as stored by `Error`.
+type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// An error, along with the status of the response it turns into.
+///
+/// Any type implementing [`std::error::Error`] converts into an `Error`, so `?` works in
+/// endpoints returning `tide::Result`. Errors converted this way are answered with
+/// `500 Internal Server Error`, except for [`BodyError`]s and [`Problem`]s, which carry their
+/// own status, and I/O and `hyper` errors caused by the client, such as a truncated request
+/// body, which are answered with `400 Bad Request`. [`Error::new`] sets the status explicitly.
+///
+/// Errors answered with a `5xx` status capture a backtrace when created, if enabled by the
+/// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables. Client errors never do, as
+/// they are common and expected, and capturing is costly.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use tide::{Error, Request, StatusCode};
+///
+/// let mut app = tide::new();
+/// app.at("/users/:id").get(|req: Request<()>| async move {
+///     let id: u64 = req.param("id").map_err(|e| Error::new(StatusCode::BAD_REQUEST, e))?;
+///     if id == 0 {
+///         return Err(Error::new(StatusCode::NOT_FOUND, "no such user"));
+///     }
+///     let name = std::fs::read_to_string(format!("users/{}", id))?;
+///     Ok(name)
+/// });
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`std::error::Error`]: https://doc.rust-lang.org/std/error/trait.Error.html
+/// [`BodyError`]: enum.BodyError.html
+/// [`Problem`]: ../struct.Problem.html
+/// [`Error::new`]: #method.new
+pub struct Error {
+    status: StatusCode,
+    error: BoxError,
+    /// The response to send, for errors created from one.
+    response: Option<Box<Response>>,
+    backtrace: Arc<Backtrace>,
+}
+
+impl Error {
+    /// Create an error answered with `status`.
+    ///
+    /// `error` can be any error type, or a message as a `&str` or `String`.
+    pub fn new(status: StatusCode, error: impl Into<BoxError>) -> Self {
+        Error {
+            status,
+            error: error.into(),
+            response: None,
+            backtrace: capture_for(status),
+        }
+    }
+
+    /// Create an error answered with `status`, with its canonical reason as the message.
+    pub fn from_status(status: StatusCode) -> Self {
+        let reason = status.canonical_reason().unwrap_or("unknown status");
+        Error::new(status, reason)
+    }
+
+    /// The status of the response this error turns into.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Change the status of the response this error turns into.
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+        if let Some(res) = self.response.take() {
+            self.response = Some(Box::new(res.set_status(status)));
+        }
+    }
+
+    /// The backtrace of where the error was created, if it was captured.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        captured(&self.backtrace)
+    }
+
+    /// The underlying error.
+    pub fn get_ref(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        &*self.error
+    }
+
+    /// The underlying error, followed by its chain of sources.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        let error: &(dyn StdError + 'static) = &*self.error;
+        std::iter::successors(Some(error), |&error| error.source())
+    }
+
+    /// Whether the underlying error is of type `E`.
+    pub fn is<E: StdError + 'static>(&self) -> bool {
+        self.error.is::<E>()
+    }
+
+    /// Get a reference to the underlying error, if it is of type `E`.
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref()
+    }
+
+    /// Get a mutable reference to the underlying error, if it is of type `E`.
+    pub fn downcast_mut<E: StdError + 'static>(&mut self) -> Option<&mut E> {
+        self.error.downcast_mut()
+    }
+
+    /// Take the underlying error, if it is of type `E`.
+    pub fn downcast<E: StdError + 'static>(self) -> std::result::Result<E, Self> {
+        let Error {
+            status,
+            error,
+            response,
+            backtrace,
+        } = self;
+        match error.downcast() {
+            Ok(error) => Ok(*error),
+            Err(error) => Err(Error {
+                status,
+                error,
+                response,
+                backtrace,
+            }),
+        }
+    }
+
+    /// Take the underlying error.
+    pub fn into_inner(self) -> BoxError {
+        self.error
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Error")
+            .field("status", &self.status)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static> From<E> for Error {
+    fn from(error: E) -> Self {
+        let error: BoxError = Box::new(error);
+        let status = default_status(&*error);
+        Error {
+            status,
+            error,
+            response: None,
+            backtrace: capture_for(status),
+        }
+    }
+}
+
+/// Use `response` as the answer to the error.
+///
+/// The error of the response, if it has one, becomes the underlying error, along with its
+/// backtrace.
+impl From<Response> for Error {
+    fn from(mut response: Response) -> Self {
+        let status = response.status();
+        let error = match response.take_cause() {
+            Some(cause) => cause,
+            None => status.canonical_reason().unwrap_or("unknown status").into(),
+        };
+        let backtrace = match response.take_backtrace() {
+            Some(backtrace) => backtrace,
+            None => capture_for(status),
+        };
+        Error {
+            status,
+            error,
+            response: Some(Box::new(response)),
+            backtrace,
+        }
+    }
 }
 
 impl IntoResponse for Error {
+    /// Turn the error into a response.
+    ///
+    /// Errors created from a response turn back into it. Otherwise, the response has the
+    /// status of the error and keeps it as its [cause], along with its backtrace. Client errors
+    /// describe the error in their body, while server errors are logged instead, keeping their
+    /// details private.
+    ///
+    /// [cause]: ../struct.Response.html#method.cause
     fn into_response(self) -> Response {
+        let Error {
+            status,
+            error,
+            response,
+            backtrace,
+        } = self;
+        let res = if let Some(response) = response {
+            *response
+        } else if let Some(e) = error.downcast_ref::<BodyError>() {
+            e.clone().into_response().set_status(status)
+        } else if let Some(problem) = error.downcast_ref::<Problem>() {
+            problem.clone().set_status(status).into_response()
+        } else if status.is_server_error() {
+            log::error!("internal error: {}", error);
+            Response::new(status.as_u16())
+        } else if status.is_client_error() {
+            Response::new(status.as_u16()).body_string(error.to_string())
+        } else {
+            Response::new(status.as_u16())
+        };
+        res.set_cause_box(error).set_backtrace(backtrace)
+    }
+}
+
+/// The status of an error converted with `From`.
+fn default_status(error: &(dyn StdError + 'static)) -> StatusCode {
+    use std::io::ErrorKind;
+
+    let client_error = if let Some(e) = error.downcast_ref::<BodyError>() {
+        return e.status();
+    } else if let Some(problem) = error.downcast_ref::<Problem>() {
+        return problem.status();
+    } else if let Some(e) = error.downcast_ref::<std::io::Error>() {
+        matches!(
+            e.kind(),
+            ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::UnexpectedEof
+        )
+    } else if let Some(e) = error.downcast_ref::<hyper::Error>() {
+        e.is_parse() || e.is_incomplete_message()
+    } else {
+        false
+    };
+    if client_error {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// The error a response was created from, stored in its extensions.
+pub(crate) struct Cause(pub(crate) BoxError);
+
+/// The backtrace of the error a response was created from, stored in its extensions.
+pub(crate) struct CauseBacktrace(pub(crate) Arc<Backtrace>);
+
+/// Capture a backtrace for errors answered with `status`, if it is a server error.
+fn capture_for(status: StatusCode) -> Arc<Backtrace> {
+    if status.is_server_error() {
+        Arc::new(Backtrace::capture())
+    } else {
+        Arc::new(Backtrace::disabled())
+    }
+}
+
+/// `backtrace`, if it was captured.
+pub(crate) fn captured(backtrace: &Backtrace) -> Option<&Backtrace> {
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace),
+        _ => None,
+    }
+}
+
+/// An error reading or decoding a request body.
+///
+/// Each kind of error maps to its own response status, see [`status`]. The response body is a
+/// JSON object describing the error, such as:
+///
+/// ```json
+/// {"error": "syntax", "message": "expected value at line 1 column 9", "line": 1, "column": 9}
+/// ```
+///
+/// [`status`]: #method.status
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyError {
+    /// The body is not valid UTF-8. Answered with `400 Bad Request`.
+    InvalidUtf8 {
+        /// The length of the valid prefix of the body, in bytes.
+        valid_up_to: usize,
+    },
+    /// The body is malformed, for example not valid JSON. Answered with `400 Bad Request`.
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The line of the error, starting at 1, when known.
+        line: Option<usize>,
+        /// The column of the error, starting at 1, when known.
+        column: Option<usize>,
+    },
+    /// The body is well-formed, but does not match the expected type, for example because it
+    /// lacks a required field. Answered with `422 Unprocessable Entity`.
+    InvalidData {
+        /// A description of the error.
+        message: String,
+        /// The line of the error, starting at 1, when known.
+        line: Option<usize>,
+        /// The column of the error, starting at 1, when known.
+        column: Option<usize>,
+    },
+    /// The `Content-Type` of the body is not the expected one. Answered with
+    /// `415 Unsupported Media Type`.
+    UnsupportedMediaType {
+        /// The expected content type.
+        expected: String,
+        /// The content type of the body, if any.
+        found: Option<String>,
+    },
+    /// The body is larger than allowed. Answered with `413 Payload Too Large`.
+    TooLarge {
+        /// The largest accepted size, in bytes.
+        limit: u64,
+    },
+}
+
+impl BodyError {
+    /// The status of the response to this error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BodyError::InvalidUtf8 { .. } | BodyError::Syntax { .. } => StatusCode::BAD_REQUEST,
+            BodyError::InvalidData { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            BodyError::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            BodyError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+
+    /// A short identifier of the kind of error, used as the `error` field of responses.
+    pub fn kind(&self) -> &'static str {
         match self {
-            Error::Response(r) => r,
-            _ => unimplemented!(),
+            BodyError::InvalidUtf8 { .. } => "invalid_utf8",
+            BodyError::Syntax { .. } => "syntax",
+            BodyError::InvalidData { .. } => "invalid_data",
+            BodyError::UnsupportedMediaType { .. } => "unsupported_media_type",
+            BodyError::TooLarge { .. } => "too_large",
+        }
+    }
+
+    /// A malformed body, without a known position.
+    pub(crate) fn syntax(message: impl Into<String>) -> Self {
+        BodyError::Syntax {
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Classify an error decoding a JSON body.
+    pub(crate) fn from_json(e: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+
+        let (line, column) = (Some(e.line()), Some(e.column()));
+        match e.classify() {
+            Category::Data => BodyError::InvalidData {
+                message: e.to_string(),
+                line,
+                column,
+            },
+            Category::Syntax | Category::Eof | Category::Io => BodyError::Syntax {
+                message: e.to_string(),
+                line,
+                column,
+            },
+        }
+    }
+
+    /// Classify an error decoding a form body.
+    ///
+    /// `serde_qs` does not expose the kind of its errors, so they are all reported as
+    /// invalid data.
+    pub(crate) fn from_form(e: serde_qs::Error) -> Self {
+        BodyError::InvalidData {
+            message: e.to_string(),
+            line: None,
+            column: None,
         }
     }
 }
 
-struct Cause(Box<dyn std::error::Error + Send + Sync>);
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::InvalidUtf8 { valid_up_to } => write!(
+                f,
+                "request body is not valid UTF-8 after {} bytes",
+                valid_up_to
+            ),
+            BodyError::Syntax { message, .. } | BodyError::InvalidData { message, .. } => {
+                f.write_str(message)
+            }
+            BodyError::UnsupportedMediaType { expected, found } => match found {
+                Some(found) => write!(f, "expected a {} body, found {}", expected, found),
+                None => write!(f, "expected a {} body", expected),
+            },
+            BodyError::TooLarge { limit } => {
+                write!(f, "request body is larger than {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl StdError for BodyError {}
+
+impl BodyError {
+    /// The JSON description of the error, as sent in responses.
+    pub(crate) fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut body = serde_json::Map::new();
+        body.insert("error".into(), self.kind().into());
+        body.insert("message".into(), self.to_string().into());
+        match self {
+            BodyError::InvalidUtf8 { valid_up_to } => {
+                body.insert("valid_up_to".into(), (*valid_up_to).into());
+            }
+            BodyError::Syntax { line, column, .. }
+            | BodyError::InvalidData { line, column, .. } => {
+                if let (Some(line), Some(column)) = (line, column) {
+                    body.insert("line".into(), (*line).into());
+                    body.insert("column".into(), (*column).into());
+                }
+            }
+            BodyError::UnsupportedMediaType { expected, .. } => {
+                body.insert("expected".into(), expected.as_str().into());
+            }
+            BodyError::TooLarge { limit } => {
+                body.insert("limit".into(), (*limit).into());
+            }
+        }
+        body
+    }
+}
 
-impl From<StatusCode> for Error {
-    fn from(status: StatusCode) -> Error {
-        Error::Response(Response::new(status.as_u16()))
+impl IntoResponse for BodyError {
+    fn into_response(self) -> Response {
+        Response::new(self.status().as_u16())
+            .body_string(serde_json::Value::Object(self.to_json()).to_string())
+            .set_mime(mime::APPLICATION_JSON)
     }
 }
 
 /// A simple error type that wraps a String
 #[derive(Debug)]
 pub struct StringError(pub String);
-impl std::error::Error for StringError {}
+impl StdError for StringError {}
 
 impl std::fmt::Display for StringError {
     fn fmt(
@@ -65,16 +490,8 @@ pub trait ResultExt<T>: Sized {
     fn with_err_status(self, status: impl Into<StatusCode>) -> Result<T>;
 }
 
-impl<T, E: std::error::Error + Send + Sync + 'static> ResultExt<T> for std::result::Result<T, E> {
+impl<T, E: StdError + Send + Sync + 'static> ResultExt<T> for std::result::Result<T, E> {
     fn with_err_status(self, status: impl Into<StatusCode>) -> Result<T> {
-        self.map_err(|e| {
-            let res = hyper::Response::builder()
-                .status(status.into())
-                .extension(Cause(Box::new(e)))
-                .body(Body::empty())
-                .unwrap()
-                .into();
-            Error::Response(res)
-        })
+        self.map_err(|e| Error::new(status.into(), e))
     }
 }
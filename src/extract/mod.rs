@@ -0,0 +1,409 @@
+//! Extracting typed values from requests.
+//!
+//! Endpoints usually start by pulling values out of the request: route parameters, the query
+//! string, the body, the state of the app. Types implementing [`FromRequest`] do this on their
+//! own, so endpoints can take them as arguments instead of a [`Request`]. Functions taking
+//! such arguments become endpoints through [`handler`].
+//!
+//! When an extractor fails, the endpoint is not called, and the request is answered with the
+//! response of the [`Error`] returned by the extractor, such as `400 Bad Request` for invalid
+//! route parameters or `415 Unsupported Media Type` for a body in the wrong format.
+//!
+//! Extractors run in the order of the arguments. Only one of them can read the body.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use futures::executor::block_on;
+//! # fn main() -> Result<(), std::io::Error> { block_on(async {
+//! #
+//! use serde::Deserialize;
+//! use tide::extract::{handler, Json, Path, Query, State};
+//!
+//! struct Db;
+//!
+//! #[derive(Deserialize)]
+//! struct Filter {
+//!     limit: Option<usize>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Comment {
+//!     text: String,
+//! }
+//!
+//! async fn add_comment(
+//!     Path(id): Path<u64>,
+//!     Query(filter): Query<Filter>,
+//!     Json(comment): Json<Comment>,
+//!     State(db): State<Db>,
+//! ) -> String {
+//!     let limit = filter.limit.unwrap_or(10);
+//!     format!("comment on post {} (limit {}): {}", id, limit, comment.text)
+//! }
+//!
+//! let mut app = tide::with_state(Db);
+//! app.at("/posts/:id/comments").post(handler(add_comment));
+//! app.listen("127.0.0.1:8080").await?;
+//! #
+//! # Ok(()) }) }
+//! ```
+//!
+//! [`FromRequest`]: trait.FromRequest.html
+//! [`Request`]: ../struct.Request.html
+//! [`handler`]: fn.handler.html
+//! [`Error`]: ../struct.Error.html
+
+use futures::future::Future;
+use hyper::StatusCode;
+use serde::de::DeserializeOwned;
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use crate::utils::BoxFuture;
+use crate::{Bytes, Endpoint, Error, IntoResponse, Request, Response};
+
+mod path;
+
+use path::ParamsDeserializer;
+
+/// A value that can be extracted from a request.
+///
+/// # Examples
+///
+/// Extracting the value of a header:
+///
+/// ```
+/// use tide::extract::{handler, FromRequest};
+/// use tide::{Error, Request, StatusCode};
+///
+/// struct ApiKey(String);
+///
+/// impl<State: Send + Sync + 'static> FromRequest<State> for ApiKey {
+///     fn from_request<'a>(
+///         req: &'a mut Request<State>,
+///     ) -> futures::future::BoxFuture<'a, Result<Self, Error>> {
+///         let key = req
+///             .headers()
+///             .get("X-Api-Key")
+///             .and_then(|value| value.to_str().ok())
+///             .map(|key| ApiKey(key.to_string()))
+///             .ok_or_else(|| Error::new(StatusCode::UNAUTHORIZED, "missing API key"));
+///         Box::pin(async move { key })
+///     }
+/// }
+///
+/// let mut app = tide::new();
+/// app.at("/key").get(handler(|ApiKey(key)| async move { key }));
+/// ```
+pub trait FromRequest<State>: Sized + Send + 'static {
+    /// Extract the value from `req`.
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>>;
+}
+
+/// Extract the value, or `None` if extracting it fails.
+///
+/// Every error of `T` becomes `None`, not only the absence of the value: a malformed `Json` body
+/// is discarded like a missing one. Extract a `Result<T, Error>` to tell them apart.
+impl<State, T> FromRequest<State> for Option<T>
+where
+    State: Send + Sync + 'static,
+    T: FromRequest<State>,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        Box::pin(async move { Ok(T::from_request(req).await.ok()) })
+    }
+}
+
+/// Extract the value, or the error to handle in the endpoint.
+impl<State, T> FromRequest<State> for Result<T, Error>
+where
+    State: Send + Sync + 'static,
+    T: FromRequest<State>,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        Box::pin(async move { Ok(T::from_request(req).await) })
+    }
+}
+
+/// Define a wrapper extractor along with its accessors.
+macro_rules! extractor {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// Take the extracted value.
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    };
+}
+
+extractor! {
+    /// The parameters of the route, deserialized into `T`.
+    ///
+    /// Structs and maps receive the parameters by name, tuples receive them in the order of the
+    /// route, and other types, such as numbers or strings, receive the only parameter of the
+    /// route. Wildcards of routes serving directories or nested apps are left out.
+    ///
+    /// Fails with `400 Bad Request` if the parameters do not deserialize into `T`.
+    ///
+    /// ```
+    /// use tide::extract::{handler, Path};
+    ///
+    /// async fn show_post(Path((user, post)): Path<(String, u64)>) -> String {
+    ///     format!("post {} of {}", post, user)
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/users/:user/posts/:post").get(handler(show_post));
+    /// ```
+    Path
+}
+
+extractor! {
+    /// The query string, deserialized into `T`.
+    ///
+    /// Fails with `400 Bad Request` if the query string does not deserialize into `T`. See
+    /// [`Request::query`].
+    ///
+    /// [`Request::query`]: ../struct.Request.html#method.query
+    Query
+}
+
+extractor! {
+    /// The JSON body, deserialized into `T`.
+    ///
+    /// Fails with the status of the [`BodyError`] if the body is not valid JSON for `T`. See
+    /// [`Request::body_json`].
+    ///
+    /// As a response, `Json` serializes `T` as `application/json`.
+    ///
+    /// [`BodyError`]: ../error/enum.BodyError.html
+    /// [`Request::body_json`]: ../struct.Request.html#method.body_json
+    Json
+}
+
+extractor! {
+    /// The `application/x-www-form-urlencoded` body, deserialized into `T`.
+    ///
+    /// Fails with the status of the [`BodyError`] if the body is not a valid form for `T`. See
+    /// [`Request::body_form`].
+    ///
+    /// As a response, `Form` serializes `T` as `application/x-www-form-urlencoded`.
+    ///
+    /// [`BodyError`]: ../error/enum.BodyError.html
+    /// [`Request::body_form`]: ../struct.Request.html#method.body_form
+    Form
+}
+
+impl<State, T> FromRequest<State> for Path<T>
+where
+    State: Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        let params = T::deserialize(ParamsDeserializer::new(route_params(req)))
+            .map(Path)
+            .map_err(|e| {
+                let message = format!("invalid route parameters: {}", e);
+                Error::new(StatusCode::BAD_REQUEST, message)
+            });
+        Box::pin(async move { params })
+    }
+}
+
+impl<State, T> FromRequest<State> for Query<T>
+where
+    State: Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        let query = req.query().map(Query);
+        Box::pin(async move { query })
+    }
+}
+
+impl<State, T> FromRequest<State> for Json<T>
+where
+    State: Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        Box::pin(async move { req.body_json().await.map(Json) })
+    }
+}
+
+impl<State, T> FromRequest<State> for Form<T>
+where
+    State: Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        Box::pin(async move { req.body_form().await.map(Form) })
+    }
+}
+
+impl<State: Send + Sync + 'static> FromRequest<State> for Bytes {
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        Box::pin(async move { req.body_bytes().await.map(Bytes) })
+    }
+}
+
+/// The state of the app.
+///
+/// ```
+/// use tide::extract::{handler, State};
+///
+/// struct Greeting(String);
+///
+/// async fn greet(State(greeting): State<Greeting>) -> String {
+///     greeting.0.clone()
+/// }
+///
+/// let mut app = tide::with_state(Greeting("hello".to_string()));
+/// app.at("/").get(handler(greet));
+/// ```
+#[derive(Debug)]
+pub struct State<T>(pub Arc<T>);
+
+impl<T> Clone for State<T> {
+    fn clone(&self) -> Self {
+        State(self.0.clone())
+    }
+}
+
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Send + Sync + 'static> FromRequest<T> for State<T> {
+    fn from_request<'a>(req: &'a mut Request<T>) -> BoxFuture<'a, Result<Self, Error>> {
+        let state = State(req.state.clone());
+        Box::pin(async move { Ok(state) })
+    }
+}
+
+/// The parameters of the routes matched by `req`, outer apps first, then in the order of the
+/// matched route.
+fn route_params<State>(req: &Request<State>) -> Vec<(&str, &str)> {
+    let mut params: Vec<_> = req
+        .route_params
+        .iter()
+        .flat_map(|params| params.iter())
+        .filter(|(name, _)| *name != "--tide-path-rest")
+        .collect();
+    if let (Some(route), Some(last)) = (req.route(), req.route_params.last()) {
+        let names: Vec<_> = route
+            .split('/')
+            .filter_map(|segment| {
+                segment
+                    .strip_prefix(':')
+                    .or_else(|| segment.strip_prefix('*'))
+            })
+            .collect();
+        let outer = params.len()
+            - last
+                .iter()
+                .filter(|(name, _)| *name != "--tide-path-rest")
+                .count();
+        params[outer..].sort_by_key(|(name, _)| names.iter().position(|n| n == name));
+    }
+    params
+}
+
+/// An endpoint calling `F` with the values extracted from the request, see [`handler`].
+///
+/// [`handler`]: fn.handler.html
+pub struct Handler<F, Args> {
+    f: F,
+    args: PhantomData<fn() -> Args>,
+}
+
+impl<F: Clone, Args> Clone for Handler<F, Args> {
+    fn clone(&self) -> Self {
+        Handler {
+            f: self.f.clone(),
+            args: PhantomData,
+        }
+    }
+}
+
+impl<F, Args> std::fmt::Debug for Handler<F, Args> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handler").finish()
+    }
+}
+
+/// Turn a function taking extractors as arguments into an endpoint.
+///
+/// Functions can take up to 8 arguments implementing [`FromRequest`]. See the [module
+/// documentation] for an example.
+///
+/// [`FromRequest`]: trait.FromRequest.html
+/// [module documentation]: index.html
+pub fn handler<F, Args>(f: F) -> Handler<F, Args> {
+    Handler {
+        f,
+        args: PhantomData,
+    }
+}
+
+macro_rules! impl_handler {
+    ($($arg:ident)*) => {
+        #[allow(non_snake_case, unused_mut, unused_variables)]
+        impl<State, F, Fut, $($arg,)*> Endpoint<State> for Handler<F, ($($arg,)*)>
+        where
+            State: Send + Sync + 'static,
+            F: Fn($($arg,)*) -> Fut + Send + Sync + 'static,
+            Fut: Future + Send + 'static,
+            Fut::Output: IntoResponse,
+            $($arg: FromRequest<State>,)*
+        {
+            fn call(&self, mut req: Request<State>) -> BoxFuture<'_, Response> {
+                Box::pin(async move {
+                    $(
+                        let $arg = match $arg::from_request(&mut req).await {
+                            Ok(value) => value,
+                            Err(e) => return e.into_response(),
+                        };
+                    )*
+                    (self.f)($($arg,)*).await.into_response()
+                })
+            }
+        }
+    };
+}
+
+impl_handler! {}
+impl_handler! { A }
+impl_handler! { A B }
+impl_handler! { A B C }
+impl_handler! { A B C D }
+impl_handler! { A B C D E }
+impl_handler! { A B C D E G }
+impl_handler! { A B C D E G H }
+impl_handler! { A B C D E G H I }
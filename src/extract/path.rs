@@ -0,0 +1,234 @@
+//! Deserialization of route parameters.
+
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{self, Error as _, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// A deserializer of the parameters of a route, as a map, a sequence, or a single value.
+pub(crate) struct ParamsDeserializer<'de> {
+    params: Vec<(&'de str, &'de str)>,
+}
+
+impl<'de> ParamsDeserializer<'de> {
+    pub(crate) fn new(params: Vec<(&'de str, &'de str)>) -> Self {
+        ParamsDeserializer { params }
+    }
+
+    /// The only parameter, for types deserialized from a single value.
+    fn single(self) -> Result<Value<'de>, Error> {
+        match self.params.as_slice() {
+            [(_, value)] => Ok(Value(value)),
+            params => Err(Error::custom(format!(
+                "expected 1 route parameter, found {}",
+                params.len()
+            ))),
+        }
+    }
+}
+
+macro_rules! deserialize_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let params = self
+            .params
+            .into_iter()
+            .map(|(name, value)| (name, Value(value)));
+        visitor.visit_map(MapDeserializer::new(params))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let values = self.params.into_iter().map(|(_, value)| Value(value));
+        visitor.visit_seq(SeqDeserializer::new(values))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    deserialize_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_option
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct map struct identifier ignored_any
+    }
+}
+
+/// The value of a route parameter, parsed into the type asked for.
+struct Value<'de>(&'de str);
+
+impl<'de> Value<'de> {
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T, Error> {
+        self.0
+            .parse()
+            .map_err(|_| Error::custom(format!("cannot parse `{}` as {}", self.0, expected)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit(self.parse(stringify!($visit).trim_start_matches("visit_"))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ParamsDeserializer;
+    use serde::Deserialize;
+
+    fn from_params<'de, T: Deserialize<'de>>(params: &[(&'de str, &'de str)]) -> Result<T, String> {
+        T::deserialize(ParamsDeserializer::new(params.to_vec())).map_err(|e| e.to_string())
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Cat,
+        Dog,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Pet {
+        id: u32,
+        kind: Kind,
+        name: Option<String>,
+    }
+
+    #[test]
+    fn deserializes_params() {
+        assert_eq!(from_params::<u32>(&[("id", "7")]), Ok(7));
+        assert_eq!(from_params::<Kind>(&[("kind", "cat")]), Ok(Kind::Cat));
+        assert_eq!(
+            from_params::<(Kind, u32)>(&[("kind", "dog"), ("id", "7")]),
+            Ok((Kind::Dog, 7))
+        );
+        assert_eq!(
+            from_params::<Pet>(&[("kind", "cat"), ("id", "7")]),
+            Ok(Pet {
+                id: 7,
+                kind: Kind::Cat,
+                name: None
+            })
+        );
+    }
+
+    #[test]
+    fn reports_invalid_params() {
+        assert_eq!(
+            from_params::<u32>(&[("id", "seven")]),
+            Err("cannot parse `seven` as u32".to_string())
+        );
+        assert_eq!(
+            from_params::<u32>(&[("kind", "cat"), ("id", "7")]),
+            Err("expected 1 route parameter, found 2".to_string())
+        );
+        assert_eq!(
+            from_params::<Pet>(&[("id", "7")]),
+            Err("missing field `kind`".to_string())
+        );
+    }
+}
@@ -0,0 +1,696 @@
+use bytes::Bytes;
+use futures::stream;
+use hyper::header::{
+    HeaderMap, ACCEPT_ENCODING, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, RANGE,
+};
+use hyper::{Body, Method, StatusCode};
+use mime::Mime;
+use tokio::io::AsyncReadExt;
+
+use std::cmp::{self, Ordering};
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "embed")]
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs::Metadata;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "embed")]
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::{parse_quality_list, BoxFuture};
+use crate::{Endpoint, Request, Response};
+
+/// The size of the chunks files are streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The largest number of ranges served from a single request. Requests asking for more get the
+/// whole file instead.
+const MAX_RANGES: usize = 32;
+
+/// The encodings of precompressed files, in order of preference, with their file extensions.
+const PRECOMPRESSED: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// An endpoint serving a single file.
+///
+/// Like [`ServeDir`], it supports range requests and answers conditional requests with
+/// `304 Not Modified`, which makes it a good fit for downloads.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use tide::fs::ServeFile;
+///
+/// let mut app = tide::new();
+/// app.at("/downloads/report.pdf").get(ServeFile::new("reports/latest.pdf"));
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`ServeDir`]: struct.ServeDir.html
+#[derive(Debug, Clone)]
+pub struct ServeFile {
+    path: PathBuf,
+    options: FileOptions,
+}
+
+impl ServeFile {
+    /// Serve the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ServeFile {
+            path: path.into(),
+            options: FileOptions::default(),
+        }
+    }
+
+    /// Prefer precompressed `.br` and `.gz` siblings of the file when the client accepts them.
+    ///
+    /// See [`ServeDir::precompressed`] for details.
+    ///
+    /// [`ServeDir::precompressed`]: struct.ServeDir.html#method.precompressed
+    pub fn precompressed(mut self, enabled: bool) -> Self {
+        self.options.precompressed = enabled;
+        self
+    }
+}
+
+impl<State: Send + Sync + 'static> Endpoint<State> for ServeFile {
+    fn call(&self, req: Request<State>) -> BoxFuture<'_, Response> {
+        Box::pin(async move {
+            let res = match tokio::fs::metadata(&self.path).await {
+                Ok(metadata) if metadata.is_file() => {
+                    let file = StaticFile::disk(self.path.clone(), &metadata);
+                    respond(&req, file, &self.options).await
+                }
+                Ok(_) => Err(io::ErrorKind::NotFound.into()),
+                Err(e) => Err(e),
+            };
+            res.unwrap_or_else(|e| error_response(&e))
+        })
+    }
+}
+
+/// How files are sent.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileOptions {
+    pub(crate) precompressed: bool,
+}
+
+/// A file to send, either from disk or embedded into the binary.
+#[derive(Debug)]
+pub(crate) struct StaticFile {
+    /// The path the MIME type of the file is guessed from.
+    path: PathBuf,
+    len: u64,
+    /// The opaque part of the entity tag of the file, without quotes.
+    tag: Option<String>,
+    last_modified: Option<SystemTime>,
+    source: Source,
+}
+
+#[derive(Debug)]
+enum Source {
+    Disk,
+    #[cfg(feature = "embed")]
+    Embedded {
+        contents: &'static [u8],
+        root: EmbeddedRoot,
+    },
+}
+
+/// A directory embedded into the binary, along with the entity tags of its files.
+///
+/// Embedded files never change, so their tags are computed once, when the directory is set up
+/// to be served.
+#[cfg(feature = "embed")]
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddedRoot {
+    pub(crate) dir: include_dir::Dir<'static>,
+    tags: Arc<HashMap<&'static Path, String>>,
+}
+
+#[cfg(feature = "embed")]
+impl EmbeddedRoot {
+    pub(crate) fn new(dir: include_dir::Dir<'static>) -> Self {
+        fn collect(dir: include_dir::Dir<'static>, tags: &mut HashMap<&'static Path, String>) {
+            for file in dir.files() {
+                tags.insert(file.path(), content_tag(file.contents()));
+            }
+            for &dir in dir.dirs() {
+                collect(dir, tags);
+            }
+        }
+
+        let mut tags = HashMap::new();
+        collect(dir, &mut tags);
+        EmbeddedRoot {
+            dir,
+            tags: Arc::new(tags),
+        }
+    }
+
+    /// The file at `path`, relative to the root.
+    pub(crate) fn file(&self, path: &Path) -> Option<StaticFile> {
+        let file = self.dir.get_file(path)?;
+        let contents = file.contents();
+        Some(StaticFile {
+            path: file.path().to_path_buf(),
+            len: contents.len() as u64,
+            tag: self.tags.get(file.path()).cloned(),
+            last_modified: None,
+            source: Source::Embedded {
+                contents,
+                root: self.clone(),
+            },
+        })
+    }
+}
+
+impl StaticFile {
+    /// A file on disk, validated by its size and modification time.
+    pub(crate) fn disk(path: PathBuf, metadata: &Metadata) -> Self {
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+        StaticFile {
+            path,
+            len,
+            tag: modified.map(|modified| disk_tag(len, modified)),
+            last_modified: modified.map(truncate_to_secs),
+            source: Source::Disk,
+        }
+    }
+
+    /// Look for the sibling of this file with the given extension appended.
+    ///
+    /// Symlinks are skipped, as they could point outside of the served directory.
+    async fn sibling(&self, extension: &str) -> io::Result<Option<StaticFile>> {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(".");
+        path.push(extension);
+        let path = PathBuf::from(path);
+        match &self.source {
+            Source::Disk => match tokio::fs::symlink_metadata(&path).await {
+                Ok(metadata) if metadata.is_file() => Ok(Some(StaticFile::disk(path, &metadata))),
+                Ok(_) => Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            },
+            #[cfg(feature = "embed")]
+            Source::Embedded { root, .. } => Ok(root.file(&path)),
+        }
+    }
+
+    /// Stream `segments`, reading the file ones from this file.
+    async fn body(&self, segments: Vec<Segment>) -> io::Result<Body> {
+        match &self.source {
+            Source::Disk => {
+                let file = tokio::fs::File::open(&self.path).await?;
+                Ok(stream_segments(file, segments))
+            }
+            #[cfg(feature = "embed")]
+            Source::Embedded { contents, .. } => {
+                let contents: &'static [u8] = contents;
+                let chunks = segments.into_iter().map(move |segment| match segment {
+                    Segment::Bytes(bytes) => Ok::<_, io::Error>(bytes),
+                    Segment::File(range) => Ok(Bytes::from_static(
+                        &contents[range.start as usize..range.end as usize],
+                    )),
+                });
+                Ok(Body::wrap_stream(stream::iter(chunks)))
+            }
+        }
+    }
+}
+
+/// Respond with the contents of `file`.
+///
+/// Conditional requests are answered with `304 Not Modified` when the file did not change, and
+/// `Range` requests with the requested parts of the file.
+pub(crate) async fn respond<State>(
+    req: &Request<State>,
+    file: StaticFile,
+    options: &FileOptions,
+) -> io::Result<Response> {
+    let mime = guess_mime(&file.path);
+    let (file, encoding) = if options.precompressed {
+        precompressed_variant(req.headers(), file).await?
+    } else {
+        (file, None)
+    };
+
+    let len = file.len;
+    let representation = Representation {
+        etag: file.tag.as_ref().map(|tag| match encoding {
+            Some(encoding) => format!("\"{}-{}\"", tag, encoding),
+            None => format!("\"{}\"", tag),
+        }),
+        last_modified: file.last_modified,
+        vary: options.precompressed,
+    };
+
+    let method = req.method();
+    if (method == Method::GET || method == Method::HEAD)
+        && representation.not_modified(req.headers())
+    {
+        return Ok(representation.apply(Response::new(StatusCode::NOT_MODIFIED.as_u16())));
+    }
+
+    let ranges = match req.headers().get(RANGE).and_then(|h| h.to_str().ok()) {
+        Some(range) if representation.if_range(req.headers()) => parse_range(range, len),
+        _ => Ranges::Full,
+    };
+
+    let res = match ranges {
+        Ranges::Unsatisfiable => {
+            return Ok(representation
+                .apply(Response::new(StatusCode::RANGE_NOT_SATISFIABLE.as_u16()))
+                .set_header("Content-Range", format!("bytes */{}", len)));
+        }
+        Ranges::Full => {
+            let body = file.body(vec![Segment::File(0..len)]).await?;
+            Response::with_reader(StatusCode::OK.as_u16(), body)
+                .set_mime(mime)
+                .set_header("Content-Length", len.to_string())
+        }
+        Ranges::Partial(ranges) if ranges.len() == 1 => {
+            let range = ranges[0].clone();
+            let body = file.body(vec![Segment::File(range.clone())]).await?;
+            Response::with_reader(StatusCode::PARTIAL_CONTENT.as_u16(), body)
+                .set_mime(mime)
+                .set_header("Content-Length", (range.end - range.start).to_string())
+                .set_header("Content-Range", content_range(&range, len))
+        }
+        Ranges::Partial(ranges) => {
+            let boundary = boundary();
+            let mut segments = Vec::with_capacity(ranges.len() * 2 + 1);
+            for range in ranges {
+                let head = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    boundary,
+                    mime,
+                    content_range(&range, len)
+                );
+                segments.push(Segment::Bytes(Bytes::from(head)));
+                segments.push(Segment::File(range));
+            }
+            segments.push(Segment::Bytes(Bytes::from(format!(
+                "\r\n--{}--\r\n",
+                boundary
+            ))));
+            let body_len: u64 = segments.iter().map(Segment::len).sum();
+            let body = file.body(segments).await?;
+            Response::with_reader(StatusCode::PARTIAL_CONTENT.as_u16(), body)
+                .set_header(
+                    "Content-Type",
+                    format!("multipart/byteranges; boundary={}", boundary),
+                )
+                .set_header("Content-Length", body_len.to_string())
+        }
+    };
+    let res = match encoding {
+        Some(encoding) => res.set_header("Content-Encoding", encoding),
+        None => res,
+    };
+    Ok(representation.apply(res))
+}
+
+/// Swap `file` for its most preferred precompressed sibling the client accepts, if any.
+async fn precompressed_variant(
+    headers: &HeaderMap,
+    file: StaticFile,
+) -> io::Result<(StaticFile, Option<&'static str>)> {
+    let accept = match headers.get(ACCEPT_ENCODING).and_then(|h| h.to_str().ok()) {
+        Some(accept) => accept,
+        None => return Ok((file, None)),
+    };
+    for (encoding, extension) in accepted_encodings(accept) {
+        if let Some(variant) = file.sibling(extension).await? {
+            return Ok((variant, Some(encoding)));
+        }
+    }
+    Ok((file, None))
+}
+
+/// The precompressed encodings allowed by an `Accept-Encoding` header, best first.
+fn accepted_encodings(accept: &str) -> Vec<(&'static str, &'static str)> {
+    let items = parse_quality_list(accept);
+    let quality = |encoding: &str| {
+        items
+            .iter()
+            .find(|item| item.value.eq_ignore_ascii_case(encoding))
+            .or_else(|| items.iter().find(|item| item.value == "*"))
+            .map_or(0.0, |item| item.quality)
+    };
+    let mut encodings: Vec<_> = PRECOMPRESSED
+        .iter()
+        .map(|&(encoding, extension)| (quality(encoding), encoding, extension))
+        .filter(|&(quality, ..)| quality > 0.0)
+        .collect();
+    // The sort is stable, so equally acceptable encodings keep our order of preference.
+    encodings.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    encodings
+        .into_iter()
+        .map(|(_, encoding, extension)| (encoding, extension))
+        .collect()
+}
+
+/// Guess the MIME type of a file from its extension.
+pub(crate) fn guess_mime(path: &Path) -> Mime {
+    mime_guess::from_path(path).first_or_octet_stream()
+}
+
+/// Answer a failure to read a file with the matching status code.
+pub(crate) fn error_response(e: &io::Error) -> Response {
+    let status = match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory => StatusCode::NOT_FOUND,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => {
+            log::error!("failed to serve a static file: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    Response::new(status.as_u16())
+}
+
+/// The version of a file being sent, used to answer conditional requests.
+struct Representation {
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+    /// Whether the file could have been sent with a different encoding.
+    vary: bool,
+}
+
+impl Representation {
+    /// Add the `ETag`, `Last-Modified`, `Accept-Ranges` and `Vary` headers to a response.
+    fn apply(&self, mut res: Response) -> Response {
+        if self.vary {
+            res = res.append_vary(ACCEPT_ENCODING);
+        }
+        if let Some(etag) = &self.etag {
+            res = res.set_header("ETag", etag);
+        }
+        if let Some(last_modified) = self.last_modified {
+            res = res.set_header("Last-Modified", httpdate::fmt_http_date(last_modified));
+        }
+        res.set_header("Accept-Ranges", "bytes")
+    }
+
+    /// Check whether the client's copy of the file is still fresh.
+    ///
+    /// `If-Modified-Since` is only consulted when there is no `If-None-Match` header.
+    fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(tags) = headers.get(IF_NONE_MATCH) {
+            let tags = match tags.to_str() {
+                Ok(tags) => tags,
+                Err(_) => return false,
+            };
+            return tags.split(',').map(str::trim).any(|tag| {
+                tag == "*"
+                    || self
+                        .etag
+                        .as_deref()
+                        .is_some_and(|etag| tag.trim_start_matches("W/") == etag)
+            });
+        }
+        let since = headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| httpdate::parse_http_date(h).ok());
+        match (since, self.last_modified) {
+            (Some(since), Some(last_modified)) => last_modified <= since,
+            _ => false,
+        }
+    }
+
+    /// Check whether the `Range` header applies, which is the case when there is no `If-Range`
+    /// header or when it matches the current version of the file.
+    fn if_range(&self, headers: &HeaderMap) -> bool {
+        let value = match headers.get(IF_RANGE) {
+            Some(value) => value.to_str().unwrap_or(""),
+            None => return true,
+        };
+        if value.starts_with('"') {
+            // Ranges can only be combined from identical representations, so weak tags never
+            // match.
+            self.etag.as_deref() == Some(value)
+        } else {
+            match (httpdate::parse_http_date(value), self.last_modified) {
+                (Ok(date), Some(last_modified)) => date == last_modified,
+                _ => false,
+            }
+        }
+    }
+}
+
+/// An entity tag derived from the size and modification time of a file.
+fn disk_tag(len: u64, modified: SystemTime) -> String {
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:x}-{:x}", len, modified)
+}
+
+/// An entity tag derived from a hash of the contents of an embedded file.
+#[cfg(feature = "embed")]
+fn content_tag(contents: &[u8]) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write(contents);
+    format!("{:x}-{:016x}", contents.len(), hasher.finish())
+}
+
+/// HTTP dates only have a resolution of seconds.
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, len)
+}
+
+/// A boundary for `multipart/byteranges` bodies, unlikely to show up inside of the file.
+fn boundary() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
+
+/// The parts of a file requested by a `Range` header.
+#[derive(Debug, PartialEq)]
+enum Ranges {
+    /// The header is invalid, uses an unknown unit or asks for more than the file, so the whole
+    /// file is sent.
+    Full,
+    /// Non-empty ranges within the file, sorted, with overlapping and adjacent ones merged.
+    Partial(Vec<Range<u64>>),
+    /// None of the ranges overlap with the file.
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header for a file of `len` bytes.
+///
+/// Requests whose ranges add up to more than the file, like `bytes=0-,0-,0-`, get the whole file
+/// rather than copies of it, as suggested by RFC 7233 §6.1.
+fn parse_range(header: &str, len: u64) -> Ranges {
+    let mut parts = header.trim().splitn(2, '=');
+    let unit = parts.next().unwrap_or("");
+    let specs = match parts.next() {
+        Some(specs) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+        _ => return Ranges::Full,
+    };
+
+    let specs: Vec<&str> = specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return Ranges::Full;
+    }
+
+    let mut ranges = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let mut bounds = spec.splitn(2, '-');
+        let (first, last) = match (bounds.next(), bounds.next()) {
+            (Some(first), Some(last)) => (first.trim(), last.trim()),
+            _ => return Ranges::Full,
+        };
+        let range = match (parse_pos(first), parse_pos(last)) {
+            // A suffix range: the last `n` bytes.
+            (None, Some(n)) if first.is_empty() => len.saturating_sub(n)..len,
+            (Some(start), None) if last.is_empty() => start..len,
+            (Some(start), Some(end)) if start <= end => start..cmp::min(end.saturating_add(1), len),
+            _ => return Ranges::Full,
+        };
+        if range.start < range.end {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.is_empty() {
+        return Ranges::Unsatisfiable;
+    }
+    let total: u64 = ranges.iter().map(|range| range.end - range.start).sum();
+    if total > len {
+        return Ranges::Full;
+    }
+    Ranges::Partial(merge_ranges(ranges))
+}
+
+/// Sort `ranges` and merge the ones overlapping or touching each other.
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn parse_pos(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// A piece of a response body.
+enum Segment {
+    Bytes(Bytes),
+    File(Range<u64>),
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        match self {
+            Segment::Bytes(bytes) => bytes.len() as u64,
+            Segment::File(range) => range.end - range.start,
+        }
+    }
+}
+
+/// Stream `segments`, reading the file ones from `file`.
+///
+/// Chunks are only read when hyper asks for them, so slow clients don't cause the file to be
+/// buffered in memory.
+fn stream_segments(file: tokio::fs::File, segments: Vec<Segment>) -> Body {
+    let state = (file, 0, VecDeque::from(segments));
+    let chunks = stream::try_unfold(state, |(mut file, mut pos, mut segments)| async move {
+        let chunk = loop {
+            match segments.front_mut() {
+                None => return Ok(None),
+                Some(Segment::Bytes(_)) => match segments.pop_front() {
+                    Some(Segment::Bytes(bytes)) => break bytes,
+                    _ => unreachable!(),
+                },
+                Some(Segment::File(range)) if range.start == range.end => {
+                    segments.pop_front();
+                }
+                Some(Segment::File(range)) => {
+                    if pos != range.start {
+                        pos = file.seek(SeekFrom::Start(range.start)).await?;
+                    }
+                    let mut buf = vec![0; cmp::min(CHUNK_SIZE as u64, range.end - pos) as usize];
+                    let read = file.read(&mut buf).await?;
+                    if read == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "file was truncated while being served",
+                        ));
+                    }
+                    buf.truncate(read);
+                    pos += read as u64;
+                    range.start = pos;
+                    break Bytes::from(buf);
+                }
+            }
+        };
+        Ok::<_, io::Error>(Some((chunk, (file, pos, segments))))
+    });
+    Body::wrap_stream(chunks)
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("bytes=0-4", 10), Ranges::Partial(vec![0..5]));
+        assert_eq!(parse_range("bytes=5-", 10), Ranges::Partial(vec![5..10]));
+        assert_eq!(parse_range("bytes=-3", 10), Ranges::Partial(vec![7..10]));
+        assert_eq!(parse_range("bytes=-30", 10), Ranges::Partial(vec![0..10]));
+        assert_eq!(parse_range("bytes=8-20", 10), Ranges::Partial(vec![8..10]));
+        assert_eq!(
+            parse_range("bytes=0-0, 2-3 ,-1", 10),
+            Ranges::Partial(vec![0..1, 2..4, 9..10])
+        );
+        assert_eq!(
+            parse_range("bytes=10-, 5-", 10),
+            Ranges::Partial(vec![5..10])
+        );
+    }
+
+    #[test]
+    fn overlapping_ranges_are_merged() {
+        assert_eq!(
+            parse_range("bytes=6-7,0-1,1-2", 10),
+            Ranges::Partial(vec![0..3, 6..8])
+        );
+        assert_eq!(
+            parse_range("bytes=0-1,2-3,-2", 10),
+            Ranges::Partial(vec![0..4, 8..10])
+        );
+        assert_eq!(parse_range("bytes=0-,0-,0-", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=0-5,3-9", 10), Ranges::Full);
+    }
+
+    #[test]
+    fn unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=10-", 10), Ranges::Unsatisfiable);
+        assert_eq!(parse_range("bytes=10-20", 10), Ranges::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 10), Ranges::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-5", 0), Ranges::Unsatisfiable);
+    }
+
+    #[test]
+    fn invalid_ranges_are_ignored() {
+        assert_eq!(parse_range("items=0-4", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=4-0", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=-", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=+1-2", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=a-b", 10), Ranges::Full);
+        assert_eq!(parse_range("bytes=", 10), Ranges::Full);
+        let many = vec!["0-0"; MAX_RANGES + 1].join(",");
+        assert_eq!(parse_range(&format!("bytes={}", many), 10), Ranges::Full);
+    }
+
+    #[test]
+    fn precompressed_encodings() {
+        let names = |accept| {
+            accepted_encodings(accept)
+                .into_iter()
+                .map(|(encoding, _)| encoding)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("gzip, deflate, br"), vec!["br", "gzip"]);
+        assert_eq!(names("br;q=0.5, gzip"), vec!["gzip", "br"]);
+        assert_eq!(names("gzip, br;q=0"), vec!["gzip"]);
+        assert_eq!(names("*"), vec!["br", "gzip"]);
+        assert_eq!(names("*, gzip;q=0"), vec!["br"]);
+        assert!(names("identity").is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use hyper::StatusCode;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::escape_html;
+use crate::{Request, Response};
+
+/// The characters percent-encoded in the links of a listing.
+const LINK: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// An entry of a directory listing.
+#[derive(Debug, Serialize)]
+pub(crate) struct ListingEntry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: Kind,
+    /// The size of files in bytes.
+    size: Option<u64>,
+    /// The modification time, in seconds since the Unix epoch.
+    modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    File,
+    Directory,
+}
+
+/// The JSON representation of a directory listing.
+#[derive(Debug, Serialize)]
+struct Listing<'a> {
+    path: &'a str,
+    entries: &'a [ListingEntry],
+}
+
+/// The query parameters of a listing request.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListingQuery {
+    sort: Option<String>,
+    order: Option<String>,
+}
+
+/// The column a listing is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortKey {
+    fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+        }
+    }
+}
+
+/// Read the entries of the directory at `path` on disk.
+///
+/// Symlinks are described by what they point to, and broken ones are skipped.
+pub(crate) async fn read_disk(path: &Path) -> io::Result<Vec<ListingEntry>> {
+    let mut dir = tokio::fs::read_dir(path).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = dir.next_entry().await? {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let metadata = match tokio::fs::metadata(entry.path()).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let kind = if metadata.is_dir() {
+            Kind::Directory
+        } else {
+            Kind::File
+        };
+        entries.push(ListingEntry {
+            name,
+            kind,
+            size: Some(metadata.len()).filter(|_| kind == Kind::File),
+            modified: metadata.modified().ok().and_then(unix_secs),
+        });
+    }
+    Ok(entries)
+}
+
+/// Read the entries of a directory embedded into the binary.
+#[cfg(feature = "embed")]
+pub(crate) fn read_embedded(dir: include_dir::Dir<'static>) -> Vec<ListingEntry> {
+    let name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let dirs = dir.dirs().iter().map(|dir| ListingEntry {
+        name: name(dir.path()),
+        kind: Kind::Directory,
+        size: None,
+        modified: None,
+    });
+    let files = dir.files().iter().map(|file| ListingEntry {
+        name: name(file.path()),
+        kind: Kind::File,
+        size: Some(file.contents().len() as u64),
+        modified: None,
+    });
+    dirs.chain(files).collect()
+}
+
+/// Respond with a listing of `entries`, as JSON if the client prefers it over HTML.
+///
+/// The `sort` query parameter sorts the listing by `name`, `size` or `modified`, and `order`
+/// sets the direction to `asc` or `desc`. Entries starting with a dot are left out unless
+/// `show_hidden` is set. HTML listings link to the parent directory unless `is_root` is set.
+pub(crate) fn respond<State>(
+    req: &Request<State>,
+    mut entries: Vec<ListingEntry>,
+    show_hidden: bool,
+    is_root: bool,
+) -> Response {
+    if !show_hidden {
+        entries.retain(|entry| !entry.name.starts_with('.'));
+    }
+
+    let query = req.query::<ListingQuery>().unwrap_or_default();
+    let key = match query.sort.as_deref() {
+        Some("size") => SortKey::Size,
+        Some("modified") => SortKey::Modified,
+        _ => SortKey::Name,
+    };
+    let descending = query.order.as_deref() == Some("desc");
+    entries.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let path = percent_decode_str(req.uri().path()).decode_utf8_lossy();
+    let formats = [mime::TEXT_HTML, mime::APPLICATION_JSON];
+    let res = if req.accepts(&formats) == Some(&mime::APPLICATION_JSON) {
+        let listing = Listing {
+            path: &path,
+            entries: &entries,
+        };
+        match serde_json::to_string(&listing) {
+            Ok(json) => Response::new(StatusCode::OK.as_u16())
+                .body_string(json)
+                .set_mime(mime::APPLICATION_JSON),
+            Err(e) => {
+                log::error!("failed to serialize a directory listing: {}", e);
+                Response::new(StatusCode::INTERNAL_SERVER_ERROR.as_u16())
+            }
+        }
+    } else {
+        Response::new(StatusCode::OK.as_u16())
+            .body_string(render_html(&path, &entries, key, descending, is_root))
+            .set_mime(mime::TEXT_HTML_UTF_8)
+    };
+    res.append_vary(hyper::header::ACCEPT)
+}
+
+fn render_html(
+    path: &str,
+    entries: &[ListingEntry],
+    key: SortKey,
+    descending: bool,
+    is_root: bool,
+) -> String {
+    let title = escape_html(path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Index of {title}</title>\n</head>\n<body>\n<h1>Index of {title}</h1>\n\
+         <table>\n<thead>\n<tr>",
+        title = title
+    );
+    for (column, label) in &[
+        (SortKey::Name, "Name"),
+        (SortKey::Size, "Size"),
+        (SortKey::Modified, "Modified"),
+    ] {
+        // Clicking the column the listing is sorted by flips the order.
+        let order = if *column == key && !descending {
+            "desc"
+        } else {
+            "asc"
+        };
+        let _ = write!(
+            html,
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            column.as_str(),
+            order,
+            label
+        );
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+    if !is_root {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.kind == Kind::Directory {
+            "/"
+        } else {
+            ""
+        };
+        let size = entry.size.map(|size| size.to_string()).unwrap_or_default();
+        let modified = entry
+            .modified
+            .map(|secs| httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(secs)))
+            .unwrap_or_default();
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{href}{suffix}\">{name}{suffix}</a></td><td>{size}</td><td>{modified}</td></tr>",
+            href = utf8_percent_encode(&entry.name, LINK),
+            name = escape_html(&entry.name),
+            suffix = suffix,
+            size = size,
+            modified = modified,
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
@@ -0,0 +1,34 @@
+//! Static file serving.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use futures::executor::block_on;
+//! # fn main() -> Result<(), std::io::Error> { block_on(async {
+//! #
+//! use tide::fs::ServeDir;
+//!
+//! let mut app = tide::new();
+//! app.at("/static").serve_dir("public/static");
+//! app.at("/docs").serve_dir(ServeDir::new("public/docs").index_files(vec!["index.htm"]));
+//! app.listen("127.0.0.1:8080").await?;
+//! #
+//! # Ok(()) }) }
+//! ```
+//!
+//! With the `embed` feature, directories can also be embedded into the binary with
+//! [`include_dir!`] and served through [`ServeDir::embedded`].
+//!
+//! [`include_dir!`]: macro.include_dir.html
+//! [`ServeDir::embedded`]: struct.ServeDir.html#method.embedded
+
+mod file;
+mod listing;
+mod serve_dir;
+
+#[cfg(feature = "embed")]
+#[cfg_attr(feature = "docs", doc(cfg(embed)))]
+pub use include_dir::{include_dir, Dir};
+
+pub use file::ServeFile;
+pub use serve_dir::ServeDir;
@@ -0,0 +1,446 @@
+use hyper::{Method, StatusCode};
+use percent_encoding::percent_decode_str;
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::guard::accepts_mime;
+use crate::utils::BoxFuture;
+use crate::{Endpoint, Request, Response};
+
+#[cfg(feature = "embed")]
+use super::file::EmbeddedRoot;
+use super::file::{self, FileOptions, StaticFile};
+use super::listing;
+
+/// The `Cache-Control` header of fingerprinted assets, see [`ServeDir::immutable_assets`].
+///
+/// [`ServeDir::immutable_assets`]: struct.ServeDir.html#method.immutable_assets
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The `Cache-Control` header of index files in single-page app mode.
+const NO_CACHE: &str = "no-cache";
+
+/// An endpoint serving the files of a directory.
+///
+/// The part of the request path matched by the wildcard of the route is resolved against the
+/// directory. Requests trying to escape the directory, through `..` segments, encoded
+/// separators or symlinks, are answered with `403 Forbidden`, and missing files with
+/// `404 Not Found`. Requests for a directory are answered with its first existing index file.
+///
+/// Files are sent with `ETag` and `Last-Modified` headers taken from their metadata. Range
+/// requests and conditional requests are supported, see [`ServeFile`].
+///
+/// `ServeDir` is usually registered through [`Route::serve_dir`], which also sets up the
+/// wildcard. A plain path converts into a `ServeDir` with the default settings.
+///
+/// # Single-page apps
+///
+/// Client-side routers expect every page to be answered with the same `index.html`, which is
+/// what [`spa_fallback`] sets up:
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use tide::fs::ServeDir;
+///
+/// let mut app = tide::new();
+/// app.at("/api/users").get(|_| async { "[]" });
+/// app.at("/").serve_dir(ServeDir::new("dist").spa_fallback("index.html").spa_exclude("/api"));
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`Route::serve_dir`]: ../struct.Route.html#method.serve_dir
+/// [`ServeFile`]: struct.ServeFile.html
+/// [`spa_fallback`]: #method.spa_fallback
+#[derive(Debug, Clone)]
+pub struct ServeDir {
+    root: Root,
+    index_files: Vec<String>,
+    options: FileOptions,
+    spa_index: Option<String>,
+    spa_exclude: Vec<String>,
+    listing: bool,
+    show_hidden: bool,
+    immutable_assets: bool,
+}
+
+impl ServeDir {
+    /// Serve the files in `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_root(Root::Disk(root.into()))
+    }
+
+    /// Serve the files of a directory embedded into the binary with [`include_dir!`].
+    ///
+    /// Embedded files are sent with an `ETag` computed from a hash of their contents, once for
+    /// all files when the `ServeDir` is created. They have no modification time, so no
+    /// `Last-Modified` header is sent.
+    ///
+    /// # Examples
+    ///
+    /// The directory is read at compile time, relative to the manifest of the crate:
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::fs::{include_dir, Dir, ServeDir};
+    ///
+    /// static ASSETS: Dir<'static> = include_dir!("tests/fixtures/static");
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/").serve_dir(ServeDir::embedded(ASSETS).precompressed(true));
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`include_dir!`]: macro.include_dir.html
+    #[cfg(feature = "embed")]
+    #[cfg_attr(feature = "docs", doc(cfg(embed)))]
+    pub fn embedded(root: include_dir::Dir<'static>) -> Self {
+        Self::with_root(Root::Embedded(EmbeddedRoot::new(root)))
+    }
+
+    fn with_root(root: Root) -> Self {
+        ServeDir {
+            root,
+            index_files: vec!["index.html".to_string()],
+            options: FileOptions::default(),
+            spa_index: None,
+            spa_exclude: Vec::new(),
+            listing: false,
+            show_hidden: false,
+            immutable_assets: false,
+        }
+    }
+
+    /// Set the files to look for when a directory is requested, in order of preference.
+    ///
+    /// Defaults to `index.html`. Passing an empty list answers directory requests with
+    /// `404 Not Found`.
+    pub fn index_files<I, S>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.index_files = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Prefer precompressed siblings of the requested files when the client accepts them.
+    ///
+    /// With this enabled, a request for `app.js` is answered with `app.js.br` or `app.js.gz`
+    /// if that file exists and the `Accept-Encoding` header allows it, preferring Brotli over
+    /// gzip. The response keeps the MIME type of `app.js`, and carries `Content-Encoding` and
+    /// `Vary: Accept-Encoding` headers. Disabled by default.
+    pub fn precompressed(mut self, enabled: bool) -> Self {
+        self.options.precompressed = enabled;
+        self
+    }
+
+    /// Serve a single-page app, answering unmatched requests with the `index` file.
+    ///
+    /// A missing file is answered with `index` when the request is a `GET` or `HEAD` request
+    /// accepting `text/html`, and the last segment of its path has no file extension. Requests
+    /// for missing assets such as `/logo.png` are still answered with `404 Not Found`.
+    ///
+    /// Index files are sent with `Cache-Control: no-cache`, so that new deployments are picked up
+    /// right away. Enable [`immutable_assets`] to also cache fingerprinted assets forever.
+    ///
+    /// [`immutable_assets`]: #method.immutable_assets
+    pub fn spa_fallback(mut self, index: impl Into<String>) -> Self {
+        self.spa_index = Some(index.into());
+        self
+    }
+
+    /// Never answer requests below `prefix` with the single-page app index.
+    ///
+    /// `prefix` is matched against whole segments of the request path, so `/api` excludes
+    /// `/api` and `/api/users`, but not `/apis`. Can be called multiple times.
+    pub fn spa_exclude(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        self.spa_exclude
+            .push(prefix.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// List the contents of directories without an index file.
+    ///
+    /// Listings are rendered as HTML, or as JSON for clients preferring `application/json`.
+    /// Both contain the name, type, size and modification time of each entry. The `sort` query
+    /// parameter sorts them by `name`, `size` or `modified`, and `order` sets the direction to
+    /// `asc` or `desc`. Disabled by default.
+    pub fn listing(mut self, enabled: bool) -> Self {
+        self.listing = enabled;
+        self
+    }
+
+    /// Send fingerprinted files with a long-lived, immutable `Cache-Control` header.
+    ///
+    /// A file is fingerprinted when a `.`, `-` or `_` separated part of its name is a hash of at
+    /// least 8 letters and digits, like `main.3f2a1b9c.js` or `index-B7f3kL2a.css`.
+    /// Dates and versions such as `backup-20201231.zip` or `report.v20201231.csv` don't count.
+    /// Only enable this when every such file is named after its contents: a file replaced under
+    /// the same name stays cached by browsers and proxies for a year. Disabled by default.
+    pub fn immutable_assets(mut self, enabled: bool) -> Self {
+        self.immutable_assets = enabled;
+        self
+    }
+
+    /// Include files and directories starting with a dot in directory listings.
+    ///
+    /// Hidden by default.
+    pub fn show_hidden(mut self, enabled: bool) -> Self {
+        self.show_hidden = enabled;
+        self
+    }
+
+    async fn serve<State>(&self, req: Request<State>) -> Response {
+        let relative = match relative_path(req.rest().unwrap_or("")) {
+            Some(relative) => relative,
+            None => return Response::new(StatusCode::FORBIDDEN.as_u16()),
+        };
+        let res = match self.serve_path(&req, &relative).await {
+            Err(e) if e.kind() == io::ErrorKind::NotFound && self.falls_back(&req) => {
+                self.serve_spa_index(&req).await
+            }
+            res => res,
+        };
+        res.unwrap_or_else(|e| file::error_response(&e))
+    }
+
+    async fn serve_path<State>(
+        &self,
+        req: &Request<State>,
+        relative: &Path,
+    ) -> io::Result<Response> {
+        let dir = match self.lookup(relative).await? {
+            Entry::File(file) => {
+                let cache_control = match &self.spa_index {
+                    Some(index) if relative == Path::new(index) => Some(NO_CACHE),
+                    _ if self.immutable_assets && is_fingerprinted(relative) => Some(IMMUTABLE),
+                    _ => None,
+                };
+                let res = file::respond(req, file, &self.options).await?;
+                return Ok(match cache_control {
+                    Some(cache_control) => res.set_header("Cache-Control", cache_control),
+                    None => res,
+                });
+            }
+            Entry::Dir(dir) => dir,
+        };
+
+        // Relative links in an index file only resolve against the directory when the URL ends
+        // with a slash.
+        let uri_path = req.uri().path();
+        if !uri_path.ends_with('/') {
+            let location = match req.uri().query() {
+                Some(query) => format!("{}/?{}", uri_path, query),
+                None => format!("{}/", uri_path),
+            };
+            return Ok(Response::new(StatusCode::MOVED_PERMANENTLY.as_u16())
+                .set_header("Location", location));
+        }
+
+        for index in &self.index_files {
+            match self.lookup(&relative.join(index)).await {
+                Ok(Entry::File(file)) => {
+                    let res = file::respond(req, file, &self.options).await?;
+                    return Ok(match self.spa_index {
+                        Some(_) => res.set_header("Cache-Control", NO_CACHE),
+                        None => res,
+                    });
+                }
+                Ok(Entry::Dir(_)) => continue,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !self.listing {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        let entries = match dir {
+            Dir::Disk(path) => listing::read_disk(&path).await?,
+            #[cfg(feature = "embed")]
+            Dir::Embedded(dir) => listing::read_embedded(dir),
+        };
+        let is_root = relative.as_os_str().is_empty();
+        Ok(listing::respond(req, entries, self.show_hidden, is_root))
+    }
+
+    /// Find the file or directory at `relative`.
+    ///
+    /// Paths leaving the directory on disk through symlinks fail with
+    /// `io::ErrorKind::PermissionDenied`.
+    async fn lookup(&self, relative: &Path) -> io::Result<Entry> {
+        match &self.root {
+            Root::Disk(root) => {
+                let root = tokio::fs::canonicalize(root).await?;
+                let path = tokio::fs::canonicalize(root.join(relative)).await?;
+                if !path.starts_with(&root) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "path leaves the served directory",
+                    ));
+                }
+                let metadata = tokio::fs::metadata(&path).await?;
+                if metadata.is_file() {
+                    Ok(Entry::File(StaticFile::disk(path, &metadata)))
+                } else if metadata.is_dir() {
+                    Ok(Entry::Dir(Dir::Disk(path)))
+                } else {
+                    Err(io::ErrorKind::NotFound.into())
+                }
+            }
+            #[cfg(feature = "embed")]
+            Root::Embedded(root) => {
+                if relative.as_os_str().is_empty() {
+                    return Ok(Entry::Dir(Dir::Embedded(root.dir)));
+                }
+                if let Some(dir) = root.dir.get_dir(relative) {
+                    return Ok(Entry::Dir(Dir::Embedded(dir)));
+                }
+                match root.file(relative) {
+                    Some(file) => Ok(Entry::File(file)),
+                    None => Err(io::ErrorKind::NotFound.into()),
+                }
+            }
+        }
+    }
+
+    /// Check whether a request for a missing file should be answered with the single-page app
+    /// index.
+    fn falls_back<State>(&self, req: &Request<State>) -> bool {
+        if self.spa_index.is_none()
+            || !(req.method() == Method::GET || req.method() == Method::HEAD)
+        {
+            return false;
+        }
+        let path = req.uri().path();
+        let excluded = self.spa_exclude.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        let last_segment = path.rsplit('/').next().unwrap_or("");
+        !excluded && !last_segment.contains('.') && accepts_mime(req.headers(), &mime::TEXT_HTML)
+    }
+
+    async fn serve_spa_index<State>(&self, req: &Request<State>) -> io::Result<Response> {
+        let index = match &self.spa_index {
+            Some(index) => index,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+        match self.lookup(Path::new(index)).await? {
+            Entry::File(file) => {
+                let res = file::respond(req, file, &self.options).await?;
+                Ok(res.set_header("Cache-Control", NO_CACHE))
+            }
+            Entry::Dir(_) => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+/// Where the files of a `ServeDir` come from.
+#[derive(Debug, Clone)]
+enum Root {
+    Disk(PathBuf),
+    #[cfg(feature = "embed")]
+    Embedded(EmbeddedRoot),
+}
+
+/// What a path of the served directory points to.
+enum Entry {
+    File(StaticFile),
+    Dir(Dir),
+}
+
+/// A directory inside of the served directory.
+enum Dir {
+    Disk(PathBuf),
+    #[cfg(feature = "embed")]
+    Embedded(include_dir::Dir<'static>),
+}
+
+impl<State: Send + Sync + 'static> Endpoint<State> for ServeDir {
+    fn call(&self, req: Request<State>) -> BoxFuture<'_, Response> {
+        Box::pin(async move { self.serve(req).await })
+    }
+}
+
+impl From<PathBuf> for ServeDir {
+    fn from(root: PathBuf) -> Self {
+        ServeDir::new(root)
+    }
+}
+
+impl From<&Path> for ServeDir {
+    fn from(root: &Path) -> Self {
+        ServeDir::new(root)
+    }
+}
+
+impl From<String> for ServeDir {
+    fn from(root: String) -> Self {
+        ServeDir::new(root)
+    }
+}
+
+impl From<&str> for ServeDir {
+    fn from(root: &str) -> Self {
+        ServeDir::new(root)
+    }
+}
+
+#[cfg(feature = "embed")]
+impl From<include_dir::Dir<'static>> for ServeDir {
+    fn from(root: include_dir::Dir<'static>) -> Self {
+        ServeDir::embedded(root)
+    }
+}
+
+/// Check whether the name of a file contains a content hash, like `main.3f2a1b9c.js` or
+/// `index-B7f3kL2a.css`.
+fn is_fingerprinted(path: &Path) -> bool {
+    let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) => stem,
+        None => return false,
+    };
+    stem.split(&['.', '-', '_'][..]).skip(1).any(|part| {
+        part.len() >= 8
+            && part.bytes().all(|b| b.is_ascii_alphanumeric())
+            && part.bytes().any(|b| b.is_ascii_digit())
+            && !is_version(part)
+    })
+}
+
+/// Check whether `part` is made of digits, with an optional `v` in front, like the dates and
+/// versions of `backup-20201231.zip` or `report.v20201231.csv`.
+fn is_version(part: &str) -> bool {
+    let digits = part.strip_prefix(&['v', 'V'][..]).unwrap_or(part);
+    digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Turn the percent-encoded remainder of a request path into a relative file system path.
+///
+/// Returns `None` if the path is not valid UTF-8 or contains segments that could escape the
+/// served directory.
+fn relative_path(rest: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for segment in rest.split('/') {
+        let segment = percent_decode_str(segment).decode_utf8().ok()?;
+        match &*segment {
+            "" | "." => continue,
+            ".." => return None,
+            s if s.contains(&['/', '\\', '\0'][..]) => return None,
+            s if cfg!(windows) && s.contains(':') => return None,
+            s => path.push(s),
+        }
+    }
+    Some(path)
+}
@@ -0,0 +1,270 @@
+//! Route guards.
+//!
+//! Guards let several endpoints share the same path and HTTP method. Each endpoint is registered
+//! together with the guards that were set on its [`Route`], and an incoming request is handed to
+//! the first endpoint whose guards all accept it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use tide::guard::{accepts, content_type, header};
+//!
+//! let mut app = tide::new();
+//! app.at("/x").guard(accepts("application/json")).get(|_| async move { "{}" });
+//! app.at("/x").guard(accepts("text/html")).get(|_| async move { "<p></p>" });
+//! app.at("/x").guard(content_type("application/json")).post(|_| async move { "json" });
+//! app.at("/x").guard(header("X-Api-Version", "2")).put(|_| async move { "v2" });
+//! ```
+//!
+//! [`Route`]: ../struct.Route.html
+
+use hyper::header::{HeaderName, ACCEPT, CONTENT_TYPE};
+use hyper::{HeaderMap, StatusCode};
+use mime::Mime;
+
+use std::sync::Arc;
+
+use crate::endpoint::DynEndpoint;
+use crate::utils::{parse_quality_list, BoxFuture};
+use crate::{Endpoint, Request, Response};
+
+/// A predicate deciding whether an endpoint can handle a request.
+///
+/// This trait is automatically implemented for `Fn(&Request<State>) -> bool` closures.
+pub trait Guard<State>: 'static + Send + Sync {
+    /// Check whether the request is accepted by this guard.
+    fn check(&self, req: &Request<State>) -> bool;
+
+    /// The status to respond with when no endpoint accepted the request and this guard was
+    /// the one that turned it down.
+    ///
+    /// Defaults to `404 Not Found`.
+    fn rejection(&self) -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+}
+
+impl<State, F> Guard<State> for F
+where
+    F: Send + Sync + 'static + Fn(&Request<State>) -> bool,
+{
+    fn check(&self, req: &Request<State>) -> bool {
+        (self)(req)
+    }
+}
+
+/// Only accept requests whose `Content-Type` matches the given MIME type.
+///
+/// The MIME type may be a range, such as `text/*`, or `*/*` to accept any declared type.
+/// Parameters like `charset` are ignored. Rejected requests, including the ones without a
+/// `Content-Type`, get a `415 Unsupported Media Type` response.
+///
+/// # Panics
+///
+/// Panics if `mime` is not a valid MIME type.
+pub fn content_type(mime: impl AsRef<str>) -> ContentType {
+    ContentType {
+        mime: parse_mime(mime.as_ref()),
+    }
+}
+
+/// Only accept requests whose `Accept` header allows the given MIME type.
+///
+/// Requests without an `Accept` header accept anything. Rejected requests get a
+/// `406 Not Acceptable` response.
+///
+/// # Panics
+///
+/// Panics if `mime` is not a valid MIME type.
+pub fn accepts(mime: impl AsRef<str>) -> Accepts {
+    Accepts {
+        mime: parse_mime(mime.as_ref()),
+    }
+}
+
+/// Only accept requests that carry the header `name` with exactly the given value.
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn header(name: impl AsRef<str>, value: impl Into<String>) -> Header {
+    let value = value.into();
+    header_with(name, move |v| v == value)
+}
+
+/// Only accept requests that carry the header `name` with a value matching the predicate.
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn header_with<F>(name: impl AsRef<str>, predicate: F) -> Header
+where
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    let name = HeaderName::from_bytes(name.as_ref().as_bytes()).expect("invalid header name");
+    Header {
+        name,
+        predicate: Arc::new(predicate),
+    }
+}
+
+fn parse_mime(mime: &str) -> Mime {
+    mime.parse().expect("invalid MIME type")
+}
+
+/// A guard on the request `Content-Type`, created by [`content_type`].
+///
+/// [`content_type`]: fn.content_type.html
+#[derive(Debug, Clone)]
+pub struct ContentType {
+    mime: Mime,
+}
+
+impl<State: 'static> Guard<State> for ContentType {
+    fn check(&self, req: &Request<State>) -> bool {
+        req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .is_some_and(|mime| {
+                (self.mime.type_() == mime::STAR || mime.type_() == self.mime.type_())
+                    && (self.mime.subtype() == mime::STAR || mime.subtype() == self.mime.subtype())
+            })
+    }
+
+    fn rejection(&self) -> StatusCode {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    }
+}
+
+/// A guard on the request `Accept` header, created by [`accepts`].
+///
+/// [`accepts`]: fn.accepts.html
+#[derive(Debug, Clone)]
+pub struct Accepts {
+    mime: Mime,
+}
+
+impl<State: 'static> Guard<State> for Accepts {
+    fn check(&self, req: &Request<State>) -> bool {
+        accepts_mime(req.headers(), &self.mime)
+    }
+
+    fn rejection(&self) -> StatusCode {
+        StatusCode::NOT_ACCEPTABLE
+    }
+}
+
+/// Check `mime` against the `Accept` headers.
+pub(crate) fn accepts_mime(headers: &HeaderMap, mime: &Mime) -> bool {
+    mime_quality(headers, mime) > 0.0
+}
+
+/// The quality the `Accept` headers give to `mime`, taken from the most specific range that
+/// matches it. Requests without an `Accept` header accept everything.
+pub(crate) fn mime_quality(headers: &HeaderMap, mime: &Mime) -> f32 {
+    let mut values = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .peekable();
+    if values.peek().is_none() {
+        return 1.0;
+    }
+
+    let mut best: Option<(u8, f32)> = None;
+    for item in values.flat_map(parse_quality_list) {
+        let range = match item.value.parse::<Mime>() {
+            Ok(range) => range,
+            Err(_) => continue,
+        };
+        let specificity = if range.type_() == mime::STAR {
+            0
+        } else if range.type_() != mime.type_() {
+            continue;
+        } else if range.subtype() == mime::STAR {
+            1
+        } else if range.subtype() == mime.subtype() {
+            2
+        } else {
+            continue;
+        };
+        match best {
+            Some((s, _)) if s >= specificity => {}
+            _ => best = Some((specificity, item.quality)),
+        }
+    }
+    best.map_or(0.0, |(_, quality)| quality)
+}
+
+/// A guard on an arbitrary request header, created by [`header`] or [`header_with`].
+///
+/// [`header`]: fn.header.html
+/// [`header_with`]: fn.header_with.html
+#[derive(Clone)]
+pub struct Header {
+    name: HeaderName,
+    predicate: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+}
+
+impl std::fmt::Debug for Header {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("Header").field("name", &self.name).finish()
+    }
+}
+
+impl<State: 'static> Guard<State> for Header {
+    fn check(&self, req: &Request<State>) -> bool {
+        req.headers()
+            .get_all(&self.name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| (self.predicate)(value))
+    }
+}
+
+/// The endpoints registered for a single path and method.
+///
+/// Guarded endpoints are tried in the order they were added. The endpoint added without guards,
+/// if any, is tried last. Adding another endpoint without guards replaces it.
+pub(crate) struct Guarded<State> {
+    candidates: Vec<Candidate<State>>,
+}
+
+type Candidate<State> = (Vec<Arc<dyn Guard<State>>>, Box<DynEndpoint<State>>);
+
+impl<State: 'static> Guarded<State> {
+    pub(crate) fn new() -> Self {
+        Guarded {
+            candidates: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, guards: Vec<Arc<dyn Guard<State>>>, ep: Box<DynEndpoint<State>>) {
+        let fallback = self
+            .candidates
+            .iter()
+            .position(|(guards, _)| guards.is_empty());
+        match (guards.is_empty(), fallback) {
+            (true, Some(index)) => self.candidates[index] = (guards, ep),
+            (false, Some(index)) => self.candidates.insert(index, (guards, ep)),
+            (_, None) => self.candidates.push((guards, ep)),
+        }
+    }
+}
+
+impl<State: 'static> Endpoint<State> for Guarded<State> {
+    fn call(&self, req: Request<State>) -> BoxFuture<'_, Response> {
+        let mut rejection = None;
+        for (guards, ep) in &self.candidates {
+            match guards.iter().find(|guard| !guard.check(&req)) {
+                None => return ep.call(req),
+                Some(guard) => {
+                    rejection.get_or_insert_with(|| guard.rejection());
+                }
+            }
+        }
+        let status = rejection.unwrap_or(StatusCode::NOT_FOUND);
+        Box::pin(async move { Response::new(status.as_u16()) })
+    }
+}
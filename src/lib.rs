@@ -8,9 +8,9 @@
 //!
 //! - __Fast:__ Written in Rust, and built on Futures, Tide is incredibly efficient.
 //! - __Friendly:__ With thorough documentation, and a complete API, Tide helps cover your every
-//!     need.
+//!   need.
 //! - __Minimal:__ With only a few concepts to learn, Tide is easy to pick up and become productive
-//!     with.
+//!   with.
 //!
 //! # Examples
 //!
@@ -119,6 +119,7 @@
 //! extended:
 //!
 //! ```no_run
+//! # #![allow(dead_code)]
 //! # use tide::Request;
 //!
 //! pub trait RequestExt {
@@ -174,8 +175,14 @@
 #![doc(test(attr(allow(unused_extern_crates, unused_variables))))]
 
 mod endpoint;
-mod error;
+pub mod error;
+pub mod extract;
+pub mod fs;
+pub mod guard;
 pub mod middleware;
+pub mod multipart;
+mod negotiate;
+mod problem;
 mod redirect;
 mod request;
 mod response;
@@ -187,16 +194,19 @@ pub mod server;
 
 pub use endpoint::Endpoint;
 pub use error::{Error, Result, ResultExt};
+pub use extract::{Form, Json};
+pub use negotiate::Negotiated;
 pub use redirect::redirect;
+pub use problem::Problem;
 pub use request::Request;
 pub use hyper::*;
 
 #[doc(inline)]
 pub use middleware::{Middleware, Next};
 #[doc(inline)]
-pub use response::{IntoResponse, Response};
+pub use response::{Bytes, CacheControl, ContentDisposition, Html, IntoResponse, Response};
 #[doc(inline)]
-pub use server::{Route, Server};
+pub use server::{Route, Scope, Server};
 
 /// Create a new Tide server.
 ///
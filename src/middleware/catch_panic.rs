@@ -0,0 +1,132 @@
+use futures::future::FutureExt;
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Once};
+
+use crate::middleware::{Middleware, Next};
+use crate::utils::BoxFuture;
+use crate::{Request, Response};
+
+thread_local! {
+    /// The location and backtrace of the last panic of the current thread.
+    static LAST_PANIC: RefCell<Option<(Option<String>, Backtrace)>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+
+/// Record the location and backtrace of panics, which their payload does not carry, before
+/// handing them to the hook that was installed before.
+fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(ToString::to_string);
+            let backtrace = Backtrace::capture();
+            LAST_PANIC.with(|cell| *cell.borrow_mut() = Some((location, backtrace)));
+            previous(info);
+        }));
+    });
+}
+
+/// Middleware turning panics of the rest of the chain into `500 Internal Server Error`
+/// responses.
+///
+/// Without it, a panicking endpoint takes down the connection it is serving, and the client
+/// sees the connection reset instead of a response. Panics are logged with their message and
+/// location, and the message is kept as the [`Response::cause`] of the response, along with
+/// the backtrace of the panic. Only unwinding panics are caught: with `panic = "abort"`, the
+/// process still aborts.
+///
+/// Add it before other middleware to catch their panics too.
+///
+/// # Panic hook
+///
+/// Panic payloads carry neither the location nor the backtrace of the panic, so the first
+/// `CatchPanic` created installs a process-wide panic hook recording them. The hook runs for
+/// every panic on every thread, inside of the app or not, and captures a backtrace each time
+/// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` enables them. It then calls the hook that was
+/// installed before, so the default message is still printed.
+///
+/// A hook installed later with [`std::panic::set_hook`] replaces this one: responses then lose
+/// the location and backtrace of the panic, and only keep its message. Install custom hooks
+/// before creating the middleware to keep both.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut app = tide::new();
+/// app.middleware(tide::middleware::CatchPanic::new());
+/// ```
+///
+/// [`Response::cause`]: ../struct.Response.html#method.cause
+/// [`std::panic::set_hook`]: https://doc.rust-lang.org/std/panic/fn.set_hook.html
+#[derive(Debug, Clone)]
+pub struct CatchPanic {
+    details: bool,
+}
+
+impl CatchPanic {
+    /// Creates a new CatchPanic middleware, installing the [panic hook] on first use.
+    ///
+    /// [panic hook]: #panic-hook
+    pub fn new() -> Self {
+        install_hook();
+        Self { details: false }
+    }
+
+    /// Describe the panic in the body of the response.
+    ///
+    /// This is meant for development: panic messages may reveal details of the app that
+    /// clients should not see.
+    pub fn details(mut self, details: bool) -> Self {
+        self.details = details;
+        self
+    }
+
+    fn recover(&self, payload: Box<dyn Any + Send>) -> Response {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Box<dyn Any>".to_string(),
+            },
+        };
+        let (location, backtrace) = LAST_PANIC
+            .with(|cell| cell.borrow_mut().take())
+            .unwrap_or_else(|| (None, Backtrace::disabled()));
+        let description = match location {
+            Some(location) => format!("panicked at {}: {}", location, message),
+            None => format!("panicked: {}", message),
+        };
+        log::error!("{}", description);
+
+        let res = Response::new(500);
+        let res = if self.details {
+            res.body_string(description.clone())
+        } else {
+            res
+        };
+        res.set_cause_box(description.into())
+            .set_backtrace(Arc::new(backtrace))
+    }
+}
+
+impl Default for CatchPanic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for CatchPanic {
+    fn handle<'a>(&'a self, req: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            match AssertUnwindSafe(next.run(req)).catch_unwind().await {
+                Ok(res) => res,
+                Err(payload) => self.recover(payload),
+            }
+        })
+    }
+}
@@ -28,7 +28,7 @@ pub(crate) struct CookiesMiddleware;
 impl CookiesMiddleware {
     /// Creates a new CookiesMiddleware.
     pub fn new() -> Self {
-        Self::default()
+        Self
     }
 }
 
@@ -84,7 +84,7 @@ impl CookieData {
             let mut jar = CookieJar::new();
 
             // as long as we have an ascii string this will start parsing the cookie
-            if let Some(raw_str) = raw.to_str().ok() {
+            if let Ok(raw_str) = raw.to_str() {
                 raw_str
                     .split(';')
                     .try_for_each(|s| -> Result<_, ParseError> {
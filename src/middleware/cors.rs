@@ -50,10 +50,7 @@ impl Cors {
 
     /// Set allow_credentials and return new Cors
     pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
-        self.allow_credentials = match HeaderValue::from_str(&allow_credentials.to_string()) {
-            Ok(header) => Some(header),
-            Err(_) => None,
-        };
+        self.allow_credentials = HeaderValue::from_str(&allow_credentials.to_string()).ok();
         self
     }
 
@@ -189,7 +186,7 @@ impl<State: Send + Sync + 'static> Middleware<State> for Cors {
                     .headers_mut()
                     .append(header::ACCESS_CONTROL_EXPOSE_HEADERS, expose_headers);
             }
-            response.into()
+            response
         })
     }
 }
@@ -0,0 +1,207 @@
+use hyper::{HeaderMap, Method, Uri, Version};
+
+use std::fmt::Write;
+
+use crate::middleware::cookies::CookieData;
+use crate::middleware::{Middleware, Next};
+use crate::server::errors::{copy_headers, is_plain_error};
+use crate::utils::{escape_html, BoxFuture};
+use crate::{Request, Response};
+
+/// Middleware rendering detailed HTML pages for error responses, meant for development.
+///
+/// Error pages show the error chain and backtrace of the response's [cause], the route that
+/// matched the request and its parameters, and the headers and cookies of the request.
+/// Backtraces are only captured for server errors and panics, when enabled by the
+/// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables.
+///
+/// The middleware is disabled unless explicitly enabled, as the pages expose details of both
+/// the app and the request. Only responses standing for an error without describing it are
+/// rendered: error responses with a body of their own are left alone.
+///
+/// # Examples
+///
+/// Enabling error pages in debug builds only:
+///
+/// ```rust
+/// use tide::middleware::DevErrors;
+///
+/// let mut app = tide::new();
+/// app.middleware(DevErrors::new().enabled(cfg!(debug_assertions)));
+/// ```
+///
+/// [cause]: ../struct.Response.html#method.cause
+#[derive(Debug, Clone, Default)]
+pub struct DevErrors {
+    enabled: bool,
+}
+
+impl DevErrors {
+    /// Creates a new, disabled, DevErrors middleware.
+    pub fn new() -> Self {
+        Self { enabled: false }
+    }
+
+    /// Set whether error pages are rendered.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for DevErrors {
+    fn handle<'a>(&'a self, req: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if !self.enabled {
+                return next.run(req).await;
+            }
+            let dump = RequestDump::new(&req);
+            let mut res = next.run(req).await;
+            let status = res.status();
+            if (status.is_client_error() || status.is_server_error()) && is_plain_error(&mut res) {
+                dump.render(res)
+            } else {
+                res
+            }
+        })
+    }
+}
+
+/// What the error pages show of a request, taken before the request is handed down.
+struct RequestDump {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+    route: Option<String>,
+    params: Vec<(String, String)>,
+    cookies: Vec<(String, String)>,
+}
+
+impl RequestDump {
+    fn new<State>(req: &Request<State>) -> Self {
+        let params = req
+            .route_params
+            .iter()
+            .flat_map(|params| params.iter())
+            .filter(|(name, _)| *name != "--tide-path-rest")
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let cookies = match req.local::<CookieData>() {
+            Some(data) => data
+                .content
+                .read()
+                .unwrap()
+                .iter()
+                .map(|cookie| (cookie.name().to_string(), cookie.value().to_string()))
+                .collect(),
+            None => Vec::new(),
+        };
+        RequestDump {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            version: req.version(),
+            headers: req.headers().clone(),
+            route: req.route().map(String::from),
+            params,
+            cookies,
+        }
+    }
+
+    /// Replace the error response `res` with a page describing it, keeping its headers,
+    /// cause and backtrace.
+    fn render(self, mut res: Response) -> Response {
+        let status = res.status();
+        let title = format!(
+            "{} {}",
+            status.as_str(),
+            status.canonical_reason().unwrap_or_default()
+        );
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape_html(&title)
+        );
+
+        html.push_str("<h2>Error</h2>\n");
+        match res.cause() {
+            Some(cause) => {
+                html.push_str("<ol>\n");
+                let cause: &(dyn std::error::Error + 'static) = cause;
+                for error in std::iter::successors(Some(cause), |&error| error.source()) {
+                    let _ = writeln!(html, "<li>{}</li>", escape_html(&error.to_string()));
+                }
+                html.push_str("</ol>\n");
+            }
+            None => html.push_str("<p>The response has no cause.</p>\n"),
+        }
+
+        html.push_str("<h2>Backtrace</h2>\n");
+        match res.backtrace() {
+            Some(backtrace) => {
+                let backtrace = escape_html(&backtrace.to_string());
+                let _ = writeln!(html, "<pre>{}</pre>", backtrace);
+            }
+            None => html.push_str(
+                "<p>No backtrace was captured. Backtraces are captured for server errors \
+                 when <code>RUST_BACKTRACE=1</code> is set.</p>\n",
+            ),
+        }
+
+        html.push_str("<h2>Request</h2>\n");
+        let _ = writeln!(
+            html,
+            "<p><code>{} {} {:?}</code></p>",
+            escape_html(self.method.as_str()),
+            escape_html(&self.uri.to_string()),
+            self.version
+        );
+        let route = self.route.as_deref().unwrap_or("none");
+        let _ = writeln!(html, "<p>Route: <code>{}</code></p>", escape_html(route));
+        table(&mut html, "Parameters", &self.params);
+        let headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.to_string(), value)
+            })
+            .collect();
+        table(&mut html, "Headers", &headers);
+        table(&mut html, "Cookies", &self.cookies);
+        html.push_str("</body>\n</html>\n");
+
+        let cause = res.take_cause();
+        let backtrace = res.take_backtrace();
+        let mut page = Response::new(status.as_u16())
+            .body_string(html)
+            .set_mime(mime::TEXT_HTML_UTF_8);
+        copy_headers(res.headers(), &mut page);
+        if let Some(cause) = cause {
+            page = page.set_cause_box(cause);
+        }
+        if let Some(backtrace) = backtrace {
+            page = page.set_backtrace(backtrace);
+        }
+        page
+    }
+}
+
+/// Append a table of names and values, titled `title`, to `html`.
+fn table(html: &mut String, title: &str, rows: &[(String, String)]) {
+    let _ = writeln!(html, "<h3>{}</h3>", title);
+    if rows.is_empty() {
+        html.push_str("<p>None.</p>\n");
+        return;
+    }
+    html.push_str("<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td><code>{}</code></td></tr>",
+            escape_html(name),
+            escape_html(value)
+        );
+    }
+    html.push_str("</table>\n");
+}
@@ -18,7 +18,7 @@ pub struct RequestLogger;
 
 impl RequestLogger {
     pub fn new() -> Self {
-        Self::default()
+        Self
     }
 
     async fn log_basic<'a, State: Send + Sync + 'static>(
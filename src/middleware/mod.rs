@@ -6,14 +6,18 @@ use crate::endpoint::DynEndpoint;
 use crate::utils::BoxFuture;
 use crate::{Request, Response};
 
+mod catch_panic;
 // mod compression;
 pub(crate) mod cookies;
 mod cors;
+mod dev_errors;
 // mod default_headers;
 mod logger;
 
+pub use catch_panic::CatchPanic;
 // pub use compression::{Compression, Decompression};
 pub use cors::{Cors, Origin};
+pub use dev_errors::DevErrors;
 // pub use default_headers::DefaultHeaders;
 pub use logger::RequestLogger;
 
@@ -0,0 +1,681 @@
+//! Parsing of `multipart/form-data` request bodies.
+//!
+//! [`Request::body_multipart`] gives access to the parts of a body as a [`Multipart`] stream.
+//! Each part is a [`Field`], itself a stream of the chunks of its contents. Parts are parsed
+//! while the body is received, so large uploads are never buffered in memory.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use futures::executor::block_on;
+//! # fn main() -> Result<(), std::io::Error> { block_on(async {
+//! #
+//! use tide::{Request, Response};
+//!
+//! let mut app = tide::new();
+//! app.at("/upload").post(|mut req: Request<()>| async move {
+//!     let mut multipart = req.body_multipart()?.part_limit(10 * 1024 * 1024);
+//!     while let Some(field) = multipart.next_field().await? {
+//!         println!("{:?} {:?}", field.name(), field.file_name());
+//!         let _contents = field.bytes().await?;
+//!     }
+//!     Ok::<_, tide::Error>(Response::new(204))
+//! });
+//! app.listen("127.0.0.1:8080").await?;
+//! #
+//! # Ok(()) }) }
+//! ```
+//!
+//! [`Request::body_multipart`]: ../struct.Request.html#method.body_multipart
+//! [`Multipart`]: struct.Multipart.html
+//! [`Field`]: struct.Field.html
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{ready, Stream};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE};
+use hyper::Body;
+use mime::Mime;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::de::DeserializeOwned;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+use crate::error::{BodyError, Error};
+
+/// The characters percent-encoded in the names of text fields, which keeps the brackets of
+/// nested names like `user[name]` meaningful to the form decoder.
+const FIELD_NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'[').remove(b']');
+
+/// The largest size of the headers of a single part.
+const MAX_HEADERS_SIZE: usize = 8 * 1024;
+
+/// A stream of the parts of a `multipart/form-data` body.
+///
+/// Created by [`Request::body_multipart`]. Fields have to be read in order: asking for the
+/// next field skips whatever is left of the current one.
+///
+/// [`Request::body_multipart`]: ../struct.Request.html#method.body_multipart
+pub struct Multipart {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart").finish()
+    }
+}
+
+impl Multipart {
+    /// Parse the parts of `body`, separated by `boundary`.
+    pub(crate) fn new(body: Body, boundary: &str) -> Self {
+        let mut delimiter = BytesMut::with_capacity(boundary.len() + 4);
+        delimiter.extend_from_slice(b"\r\n--");
+        delimiter.extend_from_slice(boundary.as_bytes());
+
+        // The first delimiter is usually at the very start of the body, without the line break
+        // preceding all the others.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"\r\n");
+
+        let inner = Inner {
+            body,
+            buf,
+            delimiter: delimiter.freeze(),
+            state: State::Preamble,
+            fields: 0,
+            part_limit: None,
+            total_limit: None,
+            read: 0,
+            field_read: 0,
+        };
+        Multipart {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Limit the size of the contents of each part, in bytes.
+    ///
+    /// Reading a part exceeding the limit fails with `413 Payload Too Large`. Unlimited by
+    /// default.
+    pub fn part_limit(self, limit: u64) -> Self {
+        lock(&self.inner).part_limit = Some(limit);
+        self
+    }
+
+    /// Limit the size of the whole body, in bytes.
+    ///
+    /// Reading past the limit fails with `413 Payload Too Large`. Unlimited by default.
+    pub fn total_limit(self, limit: u64) -> Self {
+        lock(&self.inner).total_limit = Some(limit);
+        self
+    }
+
+    /// Get the next field, skipping the rest of the current one.
+    ///
+    /// Returns `None` once all fields have been read.
+    pub async fn next_field(&mut self) -> Result<Option<Field>, Error> {
+        futures::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+            .await
+            .transpose()
+    }
+
+    /// Collect the fields of a form.
+    ///
+    /// Text fields are deserialized into `T`, the same way [`Request::body_form`] deserializes
+    /// url-encoded forms. File fields, the ones with a file name, are written to temporary
+    /// files, which are deleted once dropped unless they are [persisted].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use serde::Deserialize;
+    /// use tide::{Request, Response};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Upload {
+    ///     title: String,
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/upload").post(|mut req: Request<()>| async move {
+    ///     let form = req.body_multipart()?.into_form::<Upload>().await?;
+    ///     for file in &form.files {
+    ///         println!("{}: {} bytes", form.fields.title, file.size());
+    ///     }
+    ///     Ok::<_, tide::Error>(Response::new(204))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`Request::body_form`]: ../struct.Request.html#method.body_form
+    /// [persisted]: struct.SpooledFile.html#method.persist
+    pub async fn into_form<T: DeserializeOwned>(mut self) -> Result<MultipartForm<T>, Error> {
+        let mut query = String::new();
+        let mut files = Vec::new();
+        while let Some(field) = self.next_field().await? {
+            let name = field.name().unwrap_or("").to_string();
+            if field.file_name().is_some() {
+                files.push(SpooledFile::spool(name, field).await?);
+                continue;
+            }
+            let value = field.text().await?;
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.extend(utf8_percent_encode(&name, FIELD_NAME));
+            query.push('=');
+            query.extend(utf8_percent_encode(&value, NON_ALPHANUMERIC));
+        }
+        let fields = serde_qs::from_str(&query).map_err(BodyError::from_form)?;
+        Ok(MultipartForm { fields, files })
+    }
+}
+
+impl Stream for Multipart {
+    type Item = Result<Field, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut inner = lock(&self.inner);
+        let headers = match ready!(inner.poll_next_headers(cx)) {
+            Ok(Some(headers)) => headers,
+            Ok(None) => return Poll::Ready(None),
+            Err(e) => return Poll::Ready(Some(Err(e))),
+        };
+        let field = Field::new(self.inner.clone(), inner.fields, headers);
+        Poll::Ready(Some(field))
+    }
+}
+
+/// A part of a `multipart/form-data` body.
+///
+/// `Field` is a stream of the chunks of its contents. [`bytes`] and [`text`] read all of it
+/// at once.
+///
+/// [`bytes`]: #method.bytes
+/// [`text`]: #method.text
+pub struct Field {
+    inner: Arc<Mutex<Inner>>,
+    index: usize,
+    name: Option<String>,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+    headers: HeaderMap,
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name)
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl Field {
+    fn new(inner: Arc<Mutex<Inner>>, index: usize, headers: HeaderMap) -> Result<Self, Error> {
+        let disposition = headers
+            .get(CONTENT_DISPOSITION)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|_| malformed("invalid Content-Disposition header in multipart body"))
+            })
+            .transpose()?;
+        let (name, file_name) = match disposition {
+            Some(disposition) => (
+                disposition_param(disposition, "name"),
+                disposition_param(disposition, "filename*")
+                    .and_then(|value| decode_ext_value(&value))
+                    .or_else(|| disposition_param(disposition, "filename")),
+            ),
+            None => (None, None),
+        };
+        let content_type = match headers.get(CONTENT_TYPE) {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| malformed("invalid Content-Type header in multipart body"))?,
+            ),
+            None => None,
+        };
+        Ok(Field {
+            inner,
+            index,
+            name,
+            file_name,
+            content_type,
+            headers,
+        })
+    }
+
+    /// The name of the field.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The name of the uploaded file, for file fields.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// The content type of the field.
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// All headers of the field.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Read the next chunk of the field, or `None` at its end.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, Error> {
+        futures::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+            .await
+            .transpose()
+    }
+
+    /// Read the entire contents of the field.
+    pub async fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+
+    /// Read the entire contents of the field into a string.
+    pub async fn text(self) -> Result<String, Error> {
+        String::from_utf8(self.bytes().await?).map_err(|e| {
+            let valid_up_to = e.utf8_error().valid_up_to();
+            BodyError::InvalidUtf8 { valid_up_to }.into()
+        })
+    }
+}
+
+impl Stream for Field {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        lock(&self.inner).poll_field_chunk(cx, self.index)
+    }
+}
+
+/// The fields of a form, collected by [`Multipart::into_form`].
+///
+/// [`Multipart::into_form`]: struct.Multipart.html#method.into_form
+#[derive(Debug)]
+pub struct MultipartForm<T> {
+    /// The text fields of the form.
+    pub fields: T,
+    /// The file fields of the form, in the order they were sent.
+    pub files: Vec<SpooledFile>,
+}
+
+impl<T> MultipartForm<T> {
+    /// The first file sent in the field called `name`.
+    pub fn file(&self, name: &str) -> Option<&SpooledFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+}
+
+/// An uploaded file, written to a temporary file.
+///
+/// The temporary file is deleted when the `SpooledFile` is dropped, unless it is moved to
+/// another place with [`persist`].
+///
+/// [`persist`]: #method.persist
+#[derive(Debug)]
+pub struct SpooledFile {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+    size: u64,
+    file: NamedTempFile,
+}
+
+impl SpooledFile {
+    async fn spool(name: String, mut field: Field) -> Result<Self, Error> {
+        let file = tokio::task::spawn_blocking(NamedTempFile::new)
+            .await
+            .map_err(io::Error::other)??;
+        let mut out = tokio::fs::File::from_std(file.as_file().try_clone()?);
+        let mut size = 0;
+        while let Some(chunk) = field.chunk().await? {
+            out.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        out.flush().await?;
+        Ok(SpooledFile {
+            name,
+            file_name: field.file_name.take(),
+            content_type: field.content_type.take(),
+            size,
+            file,
+        })
+    }
+
+    /// The name of the field the file was sent in.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the file, as sent by the client.
+    ///
+    /// Never use it as a path without sanitizing it first.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// The content type of the file, as sent by the client.
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// The size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Move the file to `path`, keeping it around after the `SpooledFile` is dropped.
+    pub async fn persist(self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+        let file = self.file;
+        tokio::task::spawn_blocking(move || file.persist(path).map(drop).map_err(|e| e.error))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+/// The parser shared by a `Multipart` and its fields.
+struct Inner {
+    body: Body,
+    buf: BytesMut,
+    /// A line break followed by `--` and the boundary.
+    delimiter: Bytes,
+    state: State,
+    /// The number of fields read so far.
+    fields: usize,
+    part_limit: Option<u64>,
+    total_limit: Option<u64>,
+    /// The number of bytes of the body read so far.
+    read: u64,
+    /// The number of bytes of the current field read so far.
+    field_read: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Before the first delimiter.
+    Preamble,
+    /// Right after a delimiter, which either ends the body or starts a new part.
+    Delimiter,
+    /// In the headers of a part.
+    Headers,
+    /// In the contents of the field with the given index.
+    Field(usize),
+    /// After the final delimiter.
+    Done,
+}
+
+impl Inner {
+    /// Read the next chunk of the body into the buffer, returning `false` at its end.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<bool, Error>> {
+        match ready!(Pin::new(&mut self.body).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                self.read += chunk.len() as u64;
+                if let Some(limit) = self.total_limit.filter(|&limit| self.read > limit) {
+                    return Poll::Ready(Err(BodyError::TooLarge { limit }.into()));
+                }
+                self.buf.extend_from_slice(&chunk);
+                Poll::Ready(Ok(true))
+            }
+            Some(Err(e)) => Poll::Ready(Err(e.into())),
+            None => Poll::Ready(Ok(false)),
+        }
+    }
+
+    /// Read more of the body, failing if it ended before the final delimiter.
+    fn poll_fill_more(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if ready!(self.poll_fill(cx))? {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Ready(Err(malformed("multipart body ended unexpectedly")))
+        }
+    }
+
+    /// Read the next chunk of data before a delimiter, or `None` once the delimiter was reached.
+    fn poll_data(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<Bytes>, Error>> {
+        loop {
+            if let Some(pos) = find(&self.buf, &self.delimiter) {
+                if pos > 0 {
+                    return Poll::Ready(Ok(Some(self.buf.split_to(pos).freeze())));
+                }
+                self.buf.advance(self.delimiter.len());
+                self.state = State::Delimiter;
+                return Poll::Ready(Ok(None));
+            }
+            // The end of the buffer could be the start of a delimiter.
+            let safe = self.buf.len().saturating_sub(self.delimiter.len() - 1);
+            if safe > 0 {
+                return Poll::Ready(Ok(Some(self.buf.split_to(safe).freeze())));
+            }
+            ready!(self.poll_fill_more(cx))?;
+        }
+    }
+
+    /// Skip to the headers of the next part and parse them.
+    fn poll_next_headers(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Error>> {
+        loop {
+            match self.state {
+                State::Preamble | State::Field(_) => {
+                    ready!(self.poll_data(cx))?;
+                }
+                State::Delimiter => {
+                    if self.buf.len() < 2 {
+                        ready!(self.poll_fill_more(cx))?;
+                        continue;
+                    }
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::Done;
+                        continue;
+                    }
+                    match find(&self.buf, b"\r\n") {
+                        // Only whitespace may follow the boundary on its line.
+                        Some(pos) if self.buf[..pos].iter().all(|&b| b == b' ' || b == b'\t') => {
+                            self.buf.advance(pos + 2);
+                            self.state = State::Headers;
+                        }
+                        Some(_) => {
+                            return Poll::Ready(Err(malformed("invalid multipart boundary")))
+                        }
+                        None if self.buf.len() > MAX_HEADERS_SIZE => {
+                            return Poll::Ready(Err(malformed("invalid multipart boundary")))
+                        }
+                        None => ready!(self.poll_fill_more(cx))?,
+                    }
+                }
+                State::Headers => {
+                    let end = if self.buf.starts_with(b"\r\n") {
+                        Some(0)
+                    } else {
+                        find(&self.buf, b"\r\n\r\n").map(|pos| pos + 2)
+                    };
+                    match end {
+                        Some(end) if end <= MAX_HEADERS_SIZE => {
+                            let head = self.buf.split_to(end + 2);
+                            let headers = parse_headers(&head[..end])?;
+                            self.fields += 1;
+                            self.field_read = 0;
+                            self.state = State::Field(self.fields);
+                            return Poll::Ready(Ok(Some(headers)));
+                        }
+                        None if self.buf.len() <= MAX_HEADERS_SIZE => {
+                            ready!(self.poll_fill_more(cx))?
+                        }
+                        _ => {
+                            return Poll::Ready(Err(malformed(
+                                "headers of multipart field are too large",
+                            )))
+                        }
+                    }
+                }
+                State::Done => return Poll::Ready(Ok(None)),
+            }
+        }
+    }
+
+    /// Read the next chunk of the field with the given index.
+    fn poll_field_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+        index: usize,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        if self.state != State::Field(index) {
+            return Poll::Ready(None);
+        }
+        match ready!(self.poll_data(cx)) {
+            Ok(Some(chunk)) => {
+                self.field_read += chunk.len() as u64;
+                if let Some(limit) = self.part_limit.filter(|&limit| self.field_read > limit) {
+                    return Poll::Ready(Some(Err(BodyError::TooLarge { limit }.into())));
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Ok(None) => Poll::Ready(None),
+            Err(e) => Poll::Ready(Some(Err(e))),
+        }
+    }
+}
+
+fn lock(inner: &Mutex<Inner>) -> MutexGuard<'_, Inner> {
+    inner.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn parse_headers(head: &[u8]) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+    for line in head.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        let colon = line
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or_else(|| malformed("invalid header in multipart body"))?;
+        let name = HeaderName::from_bytes(&line[..colon])
+            .map_err(|_| malformed("invalid header name in multipart body"))?;
+        let value = HeaderValue::from_bytes(line[colon + 1..].trim_ascii())
+            .map_err(|_| malformed("invalid header value in multipart body"))?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+/// Get a parameter of a `Content-Disposition` header, like the `name` of
+/// `form-data; name="field"`.
+fn disposition_param(disposition: &str, name: &str) -> Option<String> {
+    let mut rest = disposition.split_once(';')?.1;
+    loop {
+        let (key, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (_, '\\') => value.push(chars.next()?.1),
+                    (i, '"') => break i + 1,
+                    (_, c) => value.push(c),
+                }
+            };
+            (
+                value,
+                quoted[end..].split_once(';').map_or("", |(_, after)| after),
+            )
+        } else {
+            let (value, after) = after.split_once(';').unwrap_or((after, ""));
+            (value.trim().to_string(), after)
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = after;
+    }
+}
+
+/// Decode an extended parameter value like `UTF-8''na%C3%AFve.txt`, as defined in RFC 5987.
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+    if !charset.eq_ignore_ascii_case("utf-8") {
+        return None;
+    }
+    percent_decode_str(encoded)
+        .decode_utf8()
+        .ok()
+        .map(|value| value.into_owned())
+}
+
+fn malformed(message: &str) -> Error {
+    BodyError::syntax(message).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn disposition_params() {
+        let disposition = r#"form-data; name="file"; filename="a \"b\"; c.txt""#;
+        assert_eq!(
+            disposition_param(disposition, "name").as_deref(),
+            Some("file")
+        );
+        assert_eq!(
+            disposition_param(disposition, "filename").as_deref(),
+            Some(r#"a "b"; c.txt"#)
+        );
+        assert_eq!(disposition_param(disposition, "size"), None);
+        assert_eq!(
+            disposition_param("form-data; NAME=plain ; x=y", "name").as_deref(),
+            Some("plain")
+        );
+        assert_eq!(
+            decode_ext_value("UTF-8''na%C3%AFve.txt").as_deref(),
+            Some("naïve.txt")
+        );
+    }
+}
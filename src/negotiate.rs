@@ -0,0 +1,174 @@
+//! Content negotiation.
+
+use hyper::header::{HeaderMap, HeaderName, ACCEPT};
+use hyper::StatusCode;
+use mime::Mime;
+
+use crate::guard::mime_quality;
+use crate::utils::{parse_quality_list, QualityItem};
+use crate::{Error, IntoResponse, Request, Response};
+
+/// The position of the highest quality, the first one among equals.
+///
+/// Values of quality `0` are never picked.
+pub(crate) fn best(qualities: impl IntoIterator<Item = f32>) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (i, quality) in qualities.into_iter().enumerate() {
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((i, quality));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+/// The entries of the `name` headers, or `None` if there are none.
+pub(crate) fn quality_items<'h>(
+    headers: &'h HeaderMap,
+    name: &HeaderName,
+) -> Option<Vec<QualityItem<'h>>> {
+    let mut values = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .peekable();
+    values.peek()?;
+    Some(values.flat_map(parse_quality_list).collect())
+}
+
+/// The quality given to a token such as an encoding or a charset, falling back to the one of
+/// `*`, and to `unlisted` when neither is listed.
+pub(crate) fn token_quality(items: &[QualityItem<'_>], token: &str, unlisted: f32) -> f32 {
+    items
+        .iter()
+        .find(|item| item.value.eq_ignore_ascii_case(token))
+        .or_else(|| items.iter().find(|item| item.value == "*"))
+        .map_or(unlisted, |item| item.quality)
+}
+
+/// The quality given to a language tag by the most specific range matching it, as defined by
+/// the basic filtering of RFC 4647: `en` matches `en` and `en-US`, but not `english`.
+pub(crate) fn language_quality(items: &[QualityItem<'_>], tag: &str) -> f32 {
+    let matches = |range: &str| {
+        range == "*"
+            || tag.eq_ignore_ascii_case(range)
+            || (tag.len() > range.len()
+                && tag.as_bytes()[range.len()] == b'-'
+                && tag[..range.len()].eq_ignore_ascii_case(range))
+    };
+    items
+        .iter()
+        .filter(|item| matches(item.value))
+        .max_by_key(|item| {
+            if item.value == "*" {
+                0
+            } else {
+                item.value.len()
+            }
+        })
+        .map_or(0.0, |item| item.quality)
+}
+
+type Renderer<'a> = Box<dyn FnOnce() -> Response + Send + 'a>;
+
+/// A response rendered in the format that suits the request best.
+///
+/// Each format is registered with the MIME type it produces, and a function rendering the
+/// response. [`respond`] picks the format the `Accept` header of the request gives the highest
+/// quality, the first registered one among equals, and sets the `Content-Type` of successful
+/// responses to its MIME type. Requests accepting none of the formats are answered with
+/// `406 Not Acceptable`. Either way, `Accept` is added to the `Vary` header of the response.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use tide::{Html, Json, Negotiated, Request};
+///
+/// let mut app = tide::new();
+/// app.at("/cats").get(|req: Request<()>| async move {
+///     let cats = vec!["nori", "chashu"];
+///     Negotiated::new()
+///         .json(|| Json(&cats))
+///         .html(|| Html(format!("<ul><li>{}</li></ul>", cats.join("</li><li>"))))
+///         .with("text/csv".parse().unwrap(), || format!("name\n{}\n", cats.join("\n")))
+///         .respond(&req)
+/// });
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`respond`]: #method.respond
+#[allow(missing_debug_implementations)]
+pub struct Negotiated<'a> {
+    formats: Vec<(Mime, Renderer<'a>)>,
+}
+
+impl<'a> Negotiated<'a> {
+    /// Create a response without any format.
+    pub fn new() -> Self {
+        Negotiated {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Add a format of type `mime`, rendered by `render`.
+    pub fn with<F, R>(mut self, mime: Mime, render: F) -> Self
+    where
+        F: FnOnce() -> R + Send + 'a,
+        R: IntoResponse,
+    {
+        self.formats
+            .push((mime, Box::new(move || render().into_response())));
+        self
+    }
+
+    /// Add an `application/json` format.
+    pub fn json<F, R>(self, render: F) -> Self
+    where
+        F: FnOnce() -> R + Send + 'a,
+        R: IntoResponse,
+    {
+        self.with(mime::APPLICATION_JSON, render)
+    }
+
+    /// Add a `text/html` format.
+    pub fn html<F, R>(self, render: F) -> Self
+    where
+        F: FnOnce() -> R + Send + 'a,
+        R: IntoResponse,
+    {
+        self.with(mime::TEXT_HTML_UTF_8, render)
+    }
+
+    /// Render the format that suits `req` best.
+    pub fn respond<State>(self, req: &Request<State>) -> Response {
+        let headers = req.headers();
+        let mut formats = self.formats;
+        let best = best(formats.iter().map(|(mime, _)| mime_quality(headers, mime)));
+        let res = match best {
+            Some(i) => {
+                let (mime, render) = formats.swap_remove(i);
+                let res = render();
+                if res.status().is_success() {
+                    res.set_mime(mime)
+                } else {
+                    res
+                }
+            }
+            None => {
+                let message = "none of the available formats is acceptable";
+                Error::new(StatusCode::NOT_ACCEPTABLE, message).into_response()
+            }
+        };
+        res.append_vary(ACCEPT)
+    }
+}
+
+impl Default for Negotiated<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
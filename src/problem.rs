@@ -0,0 +1,214 @@
+//! Problem details for HTTP APIs, as defined in RFC 7807.
+
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use std::fmt;
+
+use crate::error::BodyError;
+use crate::{Error, IntoResponse, Response};
+
+/// The content type of problem documents.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// The problem type of problems that have no more semantics than their status.
+const ABOUT_BLANK: &str = "about:blank";
+
+/// A problem document, describing an error in a machine-readable way.
+///
+/// Problems turn into `application/problem+json` responses, as defined in
+/// [RFC 7807](https://tools.ietf.org/html/rfc7807). They also are errors, so endpoints can
+/// return them with `?`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use tide::{Problem, Request, StatusCode};
+///
+/// let mut app = tide::new();
+/// app.at("/transfer").post(|_req: Request<()>| async move {
+///     let problem = Problem::new(StatusCode::FORBIDDEN)
+///         .set_type("https://example.com/probs/out-of-credit")
+///         .set_title("You do not have enough credit.")
+///         .set_detail("Your current balance is 30, but that costs 50.")
+///         .set_extension("balance", 30);
+///     Err::<String, _>(problem)?;
+///     Ok::<_, tide::Error>("sent".to_string())
+/// });
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type", default = "about_blank")]
+    problem_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+fn about_blank() -> String {
+    ABOUT_BLANK.to_string()
+}
+
+impl Problem {
+    /// Create a problem with the given status, titled after it.
+    pub fn new(status: StatusCode) -> Self {
+        Problem {
+            problem_type: about_blank(),
+            title: status.canonical_reason().map(String::from),
+            status: Some(status.as_u16()),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set the URI identifying the type of problem.
+    ///
+    /// Defaults to `about:blank`, for problems described by their status alone.
+    pub fn set_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Set a short summary of the type of problem.
+    pub fn set_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the status of the response.
+    pub fn set_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status.as_u16());
+        self
+    }
+
+    /// Set an explanation of this occurrence of the problem.
+    pub fn set_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set a URI identifying this occurrence of the problem.
+    pub fn set_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` fails to serialize, or if `name` is one of the standard members.
+    pub fn set_extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let name = name.into();
+        assert!(
+            !["type", "title", "status", "detail", "instance"].contains(&name.as_str()),
+            "`{}` is a standard problem member",
+            name
+        );
+        let value = serde_json::to_value(value).expect("invalid problem extension");
+        self.extensions.insert(name, value);
+        self
+    }
+
+    /// The URI identifying the type of problem.
+    pub fn problem_type(&self) -> &str {
+        &self.problem_type
+    }
+
+    /// The summary of the type of problem.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The status of the response.
+    ///
+    /// Invalid statuses are reported as `500 Internal Server Error`.
+    pub fn status(&self) -> StatusCode {
+        self.status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// The explanation of this occurrence of the problem.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// The URI identifying this occurrence of the problem.
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// The extension member called `name`.
+    pub fn extension(&self, name: &str) -> Option<&Value> {
+        self.extensions.get(name)
+    }
+
+    /// All extension members.
+    pub fn extensions(&self) -> &Map<String, Value> {
+        &self.extensions
+    }
+}
+
+/// Describe an error as a problem.
+///
+/// Problems are kept as they are. [`BodyError`]s become problems with their fields as
+/// extension members. Other errors become problems titled after their status, detailed by
+/// their message for client errors only, keeping the details of server errors private.
+///
+/// [`BodyError`]: error/enum.BodyError.html
+impl From<&Error> for Problem {
+    fn from(err: &Error) -> Self {
+        if let Some(problem) = err.downcast_ref::<Problem>() {
+            return problem.clone();
+        }
+        let problem = Problem::new(err.status());
+        if let Some(e) = err.downcast_ref::<BodyError>() {
+            let mut problem = problem.set_detail(e.to_string());
+            problem.extensions = e.to_json();
+            problem.extensions.remove("message");
+            problem
+        } else if err.status().is_client_error() {
+            problem.set_detail(err.to_string())
+        } else {
+            problem
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.title, &self.detail) {
+            (Some(title), Some(detail)) => write!(f, "{}: {}", title, detail),
+            (Some(message), None) | (None, Some(message)) => f.write_str(message),
+            (None, None) => f.write_str(&self.problem_type),
+        }
+    }
+}
+
+impl std::error::Error for Problem {}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        // Serializing a map of strings and JSON values cannot fail.
+        let body = serde_json::to_string(&self).unwrap_or_default();
+        Response::new(self.status().as_u16())
+            .body_string(body)
+            .set_mime(PROBLEM_JSON.parse().unwrap())
+    }
+}
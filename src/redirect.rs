@@ -30,7 +30,7 @@ pub struct Redirect {
 
 impl<State> Endpoint<State> for Redirect {
     fn call<'a>(&'a self, _req: Request<State>) -> BoxFuture<'a, Response> {
-        let res = Response::new(307).set_header("Location", &self.location);
+        let res = Response::new(307).set_location(&self.location);
         Box::pin(async move { res })
     }
 }
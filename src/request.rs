@@ -1,13 +1,21 @@
 use cookie::Cookie;
-use hyper::{HeaderMap, Method, Uri, Version, Body};
+use futures::StreamExt;
+use hyper::header::{
+    HeaderName, ACCEPT_CHARSET, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONTENT_LENGTH, CONTENT_TYPE,
+};
+use hyper::{Body, HeaderMap, Method, StatusCode, Uri, Version};
+use mime::Mime;
 use route_recognizer::Params;
 use serde::Deserialize;
 
 use std::{str::FromStr, sync::Arc};
 
 use crate::middleware::cookies::CookieData;
-use crate::error::Error;
-use bytes::Buf;
+use crate::multipart::Multipart;
+use crate::error::{BodyError, Error};
+use crate::middleware::Next;
+use crate::utils::{BoxFuture, QualityItem};
+use crate::{guard, negotiate};
 
 /// An HTTP request.
 ///
@@ -110,8 +118,51 @@ impl<State> Request<State> {
         self.request.headers()
     }
 
+    /// Get a typed HTTP header, implementing the [`headers::Header`] trait.
+    ///
+    /// Returns `Ok(None)` if the header is missing.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `400 Bad Request` if the header is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use headers::authorization::{Authorization, Bearer};
+    /// use tide::{Error, Request, StatusCode};
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/").get(|req: Request<()>| async move {
+    ///     let auth = req.typed_header::<Authorization<Bearer>>()?;
+    ///     let auth = auth.ok_or_else(|| Error::from_status(StatusCode::UNAUTHORIZED))?;
+    ///     Ok::<_, Error>(format!("token: {}", auth.0.token()))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    ///
+    /// [`headers::Header`]: https://docs.rs/headers/0.3/headers/trait.Header.html
+    #[cfg(feature = "headers")]
+    #[cfg_attr(feature = "docs", doc(cfg(headers)))]
+    pub fn typed_header<H: headers::Header>(&self) -> Result<Option<H>, Error> {
+        use headers::HeaderMapExt;
+
+        self.request.headers().typed_try_get().map_err(|_| {
+            let message = format!("invalid {} header", H::name());
+            Error::new(StatusCode::BAD_REQUEST, message)
+        })
+    }
+
     /// Get an HTTP header.
     ///
+    /// Returns `None` if the header is missing, or if its value is not visible ASCII. Such
+    /// values can be read as bytes through [`headers`].
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -129,8 +180,94 @@ impl<State> Request<State> {
     /// #
     /// # Ok(()) })}
     /// ```
+    ///
+    /// [`headers`]: #method.headers
     pub fn header(&self, key: &'static str) -> Option<&'_ str> {
-        self.request.headers().get(key).map(|h| h.to_str().unwrap())
+        self.request.headers().get(key).and_then(|h| h.to_str().ok())
+    }
+
+    /// The media type of `available` the `Accept` header gives the highest quality, as defined
+    /// in RFC 7231.
+    ///
+    /// Each type gets the quality of the most specific range matching it. The first type wins
+    /// among equals, so `available` should be ordered by preference. Requests without an
+    /// `Accept` header accept every type. Returns `None` if none is acceptable, which is usually
+    /// answered with `406 Not Acceptable`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::{Error, Request, StatusCode};
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/").get(|req: Request<()>| async move {
+    ///     let available = [mime::APPLICATION_JSON, mime::TEXT_PLAIN];
+    ///     match req.accepts(&available) {
+    ///         Some(mime) if *mime == mime::APPLICATION_JSON => Ok(r#"{"hello":"world"}"#),
+    ///         Some(_) => Ok("hello, world"),
+    ///         None => Err(Error::from_status(StatusCode::NOT_ACCEPTABLE)),
+    ///     }
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    pub fn accepts<'a>(&self, available: &'a [Mime]) -> Option<&'a Mime> {
+        let headers = self.headers();
+        negotiate::best(available.iter().map(|mime| guard::mime_quality(headers, mime)))
+            .map(|i| &available[i])
+    }
+
+    /// The content coding of `available` the `Accept-Encoding` header gives the highest
+    /// quality, such as `gzip` or `identity`.
+    ///
+    /// Codings missing from the header are not acceptable, except `identity` which is unless
+    /// explicitly excluded. Requests without the header accept every coding.
+    pub fn accepts_encoding<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.negotiate(ACCEPT_ENCODING, available, |items, coding| {
+            let unlisted = if coding.eq_ignore_ascii_case("identity") {
+                1.0
+            } else {
+                0.0
+            };
+            negotiate::token_quality(items, coding, unlisted)
+        })
+    }
+
+    /// The language tag of `available` the `Accept-Language` header gives the highest quality.
+    ///
+    /// Ranges match tags they are a prefix of, so `en` matches `en-US`, and each tag gets the
+    /// quality of the most specific range matching it. Requests without the header accept
+    /// every language.
+    pub fn accepts_language<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.negotiate(ACCEPT_LANGUAGE, available, negotiate::language_quality)
+    }
+
+    /// The charset of `available` the `Accept-Charset` header gives the highest quality.
+    ///
+    /// Charsets missing from the header are not acceptable. Requests without the header accept
+    /// every charset.
+    pub fn accepts_charset<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.negotiate(ACCEPT_CHARSET, available, |items, charset| {
+            negotiate::token_quality(items, charset, 0.0)
+        })
+    }
+
+    fn negotiate<'a>(
+        &self,
+        name: HeaderName,
+        available: &[&'a str],
+        quality: impl Fn(&[QualityItem<'_>], &str) -> f32,
+    ) -> Option<&'a str> {
+        let i = match negotiate::quality_items(self.headers(), &name) {
+            Some(items) => negotiate::best(available.iter().map(|value| quality(&items, value)))?,
+            None if available.is_empty() => return None,
+            None => 0,
+        };
+        Some(available[i])
     }
 
     /// Get a local value.
@@ -175,12 +312,97 @@ impl<State> Request<State> {
             .parse()
     }
 
+    /// The path pattern of the route that matched the request, such as `/users/:id`.
+    ///
+    /// Wildcards of routes serving directories or nested apps show as `*`. For requests
+    /// handled by a nested app, this is the pattern matched by the nested app, relative to the
+    /// path it is nested at.
+    pub fn route(&self) -> Option<&str> {
+        self.local::<MatchedRoute>().map(|route| route.0.as_str())
+    }
+
     pub(crate) fn rest(&self) -> Option<&str> {
         self.route_params
             .last()
             .and_then(|params| params.find("--tide-path-rest"))
     }
 
+    /// The largest body size in bytes accepted when reading the body, if any.
+    ///
+    /// See [`set_body_limit`] for details.
+    ///
+    /// [`set_body_limit`]: #method.set_body_limit
+    pub fn body_limit(&self) -> Option<u64> {
+        self.local::<BodyLimit>().map(|limit| limit.0)
+    }
+
+    /// Limit the size of the body read by the next calls to the `body_*` methods.
+    ///
+    /// Reading a larger body fails with `413 Payload Too Large`. The limit overrides the ones
+    /// set with [`Server::body_limit`] and [`Route::body_limit`], so endpoints can raise or lower
+    /// it right before reading the body:
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::Request;
+    ///
+    /// let mut app = tide::new();
+    /// app.body_limit(64 * 1024);
+    /// app.at("/avatar").put(|mut req: Request<()>| async move {
+    ///     let _avatar = req.set_body_limit(1024 * 1024).body_bytes().await?;
+    ///     Ok::<_, tide::Error>("")
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    ///
+    /// [`Server::body_limit`]: struct.Server.html#method.body_limit
+    /// [`Route::body_limit`]: struct.Route.html#method.body_limit
+    pub fn set_body_limit(&mut self, limit: u64) -> &mut Self {
+        self.request.extensions_mut().insert(BodyLimit(limit));
+        self
+    }
+
+    /// Get the body limit, failing early if the `Content-Length` of the body exceeds it.
+    fn check_length(&self) -> Result<Option<u64>, Error> {
+        let limit = self.body_limit();
+        let length = self
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        match (limit, length) {
+            (Some(limit), Some(length)) if length > limit => Err(BodyError::TooLarge { limit }.into()),
+            _ => Ok(limit),
+        }
+    }
+
+    /// The `Content-Type` of the body.
+    fn content_type(&self) -> Option<String> {
+        let value = self.headers().get(CONTENT_TYPE)?;
+        Some(String::from_utf8_lossy(value.as_bytes()).into_owned())
+    }
+
+    /// Fail unless the body has no `Content-Type`, or one accepted by `matches`.
+    fn expect_content_type(
+        &self,
+        expected: &str,
+        matches: impl Fn(&Mime) -> bool,
+    ) -> Result<(), Error> {
+        match self.content_type() {
+            None => Ok(()),
+            Some(found) if found.parse().is_ok_and(|mime| matches(&mime)) => Ok(()),
+            found => Err(BodyError::UnsupportedMediaType {
+                expected: expected.to_string(),
+                found,
+            }
+            .into()),
+        }
+    }
+
     /// Reads the entire request body into a byte buffer.
     ///
     /// This method can be called after the body has already been read, but will
@@ -191,6 +413,9 @@ impl<State> Request<State> {
     /// Any I/O error encountered while reading the body is immediately returned
     /// as an `Err`.
     ///
+    /// Bodies larger than the [body limit] fail with `413 Payload Too Large`, before
+    /// anything is read if their `Content-Length` is too large already.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -208,11 +433,22 @@ impl<State> Request<State> {
     /// #
     /// # Ok(()) })}
     /// ```
+    ///
+    /// [body limit]: #method.set_body_limit
     pub async fn body_bytes(&mut self) -> Result<Vec<u8>, Error> {
-        let body = std::mem::replace(self.request.body_mut(), Body::empty());
-        // todo: not a fan of these extra allocations, getting a Vec<u8> out of the body shouldn't be this hard.
-        let bytes = hyper::body::aggregate(body).await?.to_bytes();
-        Ok(bytes.to_vec())
+        let limit = self.check_length()?;
+        let mut body = self.body_raw();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            if let Some(limit) = limit {
+                if (bytes.len() + chunk.len()) as u64 > limit {
+                    return Err(BodyError::TooLarge { limit }.into());
+                }
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
     }
 
     /// Reads the entire request body into a string.
@@ -246,9 +482,10 @@ impl<State> Request<State> {
     /// ```
     pub async fn body_string(&mut self) -> Result<String, Error> {
         let body_bytes = self.body_bytes().await?;
-        Ok(String::from_utf8(body_bytes).map_err(|_| {
-            Error::IO(std::io::ErrorKind::InvalidData.into())
-        })?)
+        String::from_utf8(body_bytes).map_err(|e| {
+            let valid_up_to = e.utf8_error().valid_up_to();
+            BodyError::InvalidUtf8 { valid_up_to }.into()
+        })
     }
 
     pub fn body_raw(&mut self) -> Body {
@@ -265,10 +502,50 @@ impl<State> Request<State> {
     /// If the body cannot be interpreted as valid json for the target type `T`,
     /// an `Err` is returned.
     pub async fn body_json<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, Error> {
+        self.expect_content_type("application/json", |mime| {
+            mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON)
+        })?;
         let body_bytes = self.body_bytes().await?;
-        Ok(serde_json::from_slice(&body_bytes).map_err(|_| {
-            Error::IO(std::io::ErrorKind::InvalidData.into())
-        })?)
+        serde_json::from_slice(&body_bytes).map_err(|e| BodyError::from_json(e).into())
+    }
+
+    /// Read the body as `multipart/form-data`, one part at a time.
+    ///
+    /// The parts are parsed as the body is received, see the [`multipart`] module for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `415 Unsupported Media Type` if the body is not `multipart/form-data`, and
+    /// with `400 Bad Request` if its `Content-Type` has no boundary.
+    ///
+    /// The [body limit] becomes the [total limit] of the parts.
+    ///
+    /// [`multipart`]: multipart/index.html
+    /// [body limit]: #method.set_body_limit
+    /// [total limit]: multipart/struct.Multipart.html#method.total_limit
+    pub fn body_multipart(&mut self) -> Result<Multipart, Error> {
+        let is_form_data =
+            |mime: &Mime| mime.type_() == mime::MULTIPART && mime.subtype() == mime::FORM_DATA;
+        let found = self.content_type();
+        let mime = match found.as_deref().map(str::parse::<Mime>) {
+            Some(Ok(mime)) if is_form_data(&mime) => mime,
+            _ => {
+                return Err(BodyError::UnsupportedMediaType {
+                    expected: "multipart/form-data".to_string(),
+                    found,
+                }
+                .into())
+            }
+        };
+        let boundary = mime
+            .get_param(mime::BOUNDARY)
+            .ok_or_else(|| BodyError::syntax("missing multipart boundary"))?;
+        let limit = self.check_length()?;
+        let multipart = Multipart::new(self.body_raw(), boundary.as_str());
+        Ok(match limit {
+            Some(limit) => multipart.total_limit(limit),
+            None => multipart,
+        })
     }
 
     /// Get the URL querystring.
@@ -280,21 +557,17 @@ impl<State> Request<State> {
         serde_qs::from_str(query).map_err(|e| {
             // Return the displayable version of the deserialisation error to the caller
             // for easier debugging.
-            let response = crate::Response::new(400).body_string(format!("{}", e));
-            crate::Error::Response(response)
+            crate::Error::new(StatusCode::BAD_REQUEST, e.to_string())
         })
     }
 
     /// Parse the request body as a form.
     pub async fn body_form<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, Error> {
-        let body = self.body_bytes().await?;
-        let res = serde_qs::from_bytes(&body).map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("could not decode form: {}", e),
-            )
+        self.expect_content_type("application/x-www-form-urlencoded", |mime| {
+            mime.type_() == mime::APPLICATION && mime.subtype() == mime::WWW_FORM_URLENCODED
         })?;
-        Ok(res)
+        let body = self.body_bytes().await?;
+        serde_qs::from_bytes(&body).map_err(|e| BodyError::from_form(e).into())
     }
 
     /// returns a `Cookie` by name of the cookie.
@@ -307,3 +580,24 @@ impl<State> Request<State> {
         locked_jar.get(name).cloned()
     }
 }
+
+/// The path pattern of the route that matched a request.
+#[derive(Debug, Clone)]
+pub(crate) struct MatchedRoute(pub(crate) String);
+
+/// The largest body size accepted by the `body_*` methods of a request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BodyLimit(pub(crate) u64);
+
+/// Setting a body limit as middleware, for the limits of routes and scopes.
+impl<State: Send + Sync + 'static> crate::Middleware<State> for BodyLimit {
+    fn handle<'a>(
+        &'a self,
+        mut req: Request<State>,
+        next: Next<'a, State>,
+    ) -> BoxFuture<'a, crate::Response> {
+        req.set_body_limit(self.0);
+        next.run(req)
+    }
+}
+
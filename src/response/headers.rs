@@ -0,0 +1,190 @@
+//! Typed values of common response headers.
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+
+use std::fmt;
+use std::time::Duration;
+
+/// The value of a `Cache-Control` header.
+///
+/// ```
+/// use std::time::Duration;
+/// use tide::{CacheControl, Response};
+///
+/// let cache_control = CacheControl::new().public().max_age(Duration::from_secs(3600));
+/// assert_eq!(cache_control.to_string(), "public, max-age=3600");
+/// let res = Response::new(200).set_cache_control(cache_control);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheControl {
+    directives: Vec<String>,
+}
+
+impl CacheControl {
+    /// Create a header value without any directive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn directive(mut self, directive: impl Into<String>) -> Self {
+        self.directives.push(directive.into());
+        self
+    }
+
+    /// Add the `no-cache` directive: caches must revalidate the response before using it.
+    pub fn no_cache(self) -> Self {
+        self.directive("no-cache")
+    }
+
+    /// Add the `no-store` directive: caches must not store the response.
+    pub fn no_store(self) -> Self {
+        self.directive("no-store")
+    }
+
+    /// Add the `no-transform` directive: intermediaries must not transform the body.
+    pub fn no_transform(self) -> Self {
+        self.directive("no-transform")
+    }
+
+    /// Add the `must-revalidate` directive: caches must not use the response once stale.
+    pub fn must_revalidate(self) -> Self {
+        self.directive("must-revalidate")
+    }
+
+    /// Add the `public` directive: shared caches may store the response.
+    pub fn public(self) -> Self {
+        self.directive("public")
+    }
+
+    /// Add the `private` directive: only the cache of the client may store the response.
+    pub fn private(self) -> Self {
+        self.directive("private")
+    }
+
+    /// Add the `immutable` directive: the response never changes while fresh.
+    pub fn immutable(self) -> Self {
+        self.directive("immutable")
+    }
+
+    /// Add the `max-age` directive: the response is fresh for `age`, in whole seconds.
+    pub fn max_age(self, age: Duration) -> Self {
+        self.directive(format!("max-age={}", age.as_secs()))
+    }
+
+    /// Add the `s-maxage` directive: the response is fresh for `age` in shared caches.
+    pub fn s_max_age(self, age: Duration) -> Self {
+        self.directive(format!("s-maxage={}", age.as_secs()))
+    }
+}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.directives.join(", "))
+    }
+}
+
+/// Characters encoded in the `filename*` parameter of `Content-Disposition`, as defined in
+/// RFC 5987.
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// The value of a `Content-Disposition` header.
+///
+/// File names are escaped as needed. Names that are not plain ASCII are sent in the
+/// `filename*` parameter, with an ASCII approximation in `filename` for older clients.
+///
+/// ```
+/// use tide::{ContentDisposition, Response};
+///
+/// let disposition = ContentDisposition::attachment().filename("report \"2020\".csv");
+/// assert_eq!(
+///     disposition.to_string(),
+///     r#"attachment; filename="report \"2020\".csv""#
+/// );
+/// let res = Response::new(200).set_content_disposition(disposition);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentDisposition {
+    attachment: bool,
+    filename: Option<String>,
+}
+
+impl ContentDisposition {
+    /// Display the body in the page.
+    pub fn inline() -> Self {
+        ContentDisposition {
+            attachment: false,
+            filename: None,
+        }
+    }
+
+    /// Download the body rather than displaying it.
+    pub fn attachment() -> Self {
+        ContentDisposition {
+            attachment: true,
+            filename: None,
+        }
+    }
+
+    /// Set the name of the file to save the body as.
+    pub fn filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+}
+
+impl fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.attachment {
+            "attachment"
+        } else {
+            "inline"
+        })?;
+        let filename = match &self.filename {
+            Some(filename) => filename,
+            None => return Ok(()),
+        };
+        let mut fallback = String::with_capacity(filename.len());
+        for c in filename.chars() {
+            match c {
+                '"' | '\\' => {
+                    fallback.push('\\');
+                    fallback.push(c);
+                }
+                ' '..='~' => fallback.push(c),
+                _ => fallback.push('_'),
+            }
+        }
+        write!(f, "; filename=\"{}\"", fallback)?;
+        if filename.chars().any(|c| !(' '..='~').contains(&c)) {
+            let encoded = utf8_percent_encode(filename, ATTR_CHAR);
+            write!(f, "; filename*=UTF-8''{}", encoded)?;
+        }
+        Ok(())
+    }
+}
+
+/// Characters encoded in `Location` headers: the ones that are neither allowed in URIs nor
+/// already percent-encoded.
+pub(crate) const LOCATION: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
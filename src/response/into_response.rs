@@ -1,5 +1,7 @@
-use crate::{Request, Response};
-use hyper::StatusCode;
+use crate::extract::{Form, Json};
+use crate::{Error, Request, Response};
+use hyper::{Body, HeaderMap, StatusCode};
+use serde::Serialize;
 use serde_json::Value;
 
 /// Conversion into a `Response`.
@@ -22,30 +24,23 @@ pub trait IntoResponse: Send + Sized {
     }
 }
 
-// impl IntoResponse for () {
-//     fn into_response(self) -> Response {
-//         hyper::Response::builder()
-//             .status(hyper::StatusCode::NO_CONTENT)
-//             .body(Body::empty())
-//             .unwrap()
-//     }
-// }
+/// Respond with `204 No Content`.
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::new(StatusCode::NO_CONTENT.as_u16())
+    }
+}
 
-// impl IntoResponse for Vec<u8> {
-//     fn into_response(self) -> Response {
-//         hyper::Response::builder()
-//             .status(hyper::StatusCode::OK)
-//             .header("Content-Type", "application/octet-stream")
-//             .body(Body::from(self))
-//             .unwrap()
-//     }
-// }
+/// Respond with the bytes as `application/octet-stream`.
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        Response::new(200).body(self)
+    }
+}
 
 impl IntoResponse for Value {
     fn into_response(self) -> Response {
-        Response::new(200)
-			.set_header("Content-Type", "application/octet-stream")
-            .body_string(serde_json::to_string(&self).unwrap())
+        Json(self).into_response()
     }
 }
 
@@ -59,8 +54,7 @@ impl IntoResponse for String {
 
 impl<State: Send + Sync + 'static> IntoResponse for Request<State> {
     fn into_response(mut self) -> Response {
-        Response::new(200)
-            .body(self.body_raw())
+        Response::new(200).body(self.body_raw())
     }
 }
 
@@ -70,33 +64,142 @@ impl IntoResponse for &'_ str {
     }
 }
 
-// impl IntoResponse for hyper::StatusCode {
-//     fn into_response(self) -> Response {
-//         hyper::Response::builder()
-//             .status(self)
-//             .body(Body::empty())
-//             .unwrap()
-//     }
-// }
+/// Respond with the status and an empty body.
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Response {
+        Response::new(self.as_u16())
+    }
+}
 
+/// Respond with the body, and the status instead of its own.
+impl<R: IntoResponse> IntoResponse for (StatusCode, R) {
+    fn into_response(self) -> Response {
+        let (status, body) = self;
+        body.into_response().set_status(status)
+    }
+}
+
+/// Respond with the body, and the status and headers instead of its own.
+///
+/// Headers replace the ones of the body with the same name, such as `Content-Type`.
+impl<R: IntoResponse> IntoResponse for (StatusCode, HeaderMap, R) {
+    fn into_response(self) -> Response {
+        let (status, headers, body) = self;
+        let mut res = body.into_response().set_status(status);
+        res.response_mut().headers_mut().extend(headers);
+        res
+    }
+}
+
+/// Respond with the value as `application/json`.
+///
+/// Values failing to serialize are answered with `500 Internal Server Error`.
+impl<T: Serialize + Send> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match Response::new(200).body_json(&self.0) {
+            Ok(res) => res,
+            Err(e) => Error::new(StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        }
+    }
+}
+
+/// Respond with the value as `application/x-www-form-urlencoded`.
+///
+/// Values failing to serialize are answered with `500 Internal Server Error`.
+impl<T: Serialize + Send> IntoResponse for Form<T> {
+    fn into_response(self) -> Response {
+        match serde_qs::to_string(&self.0) {
+            Ok(form) => Response::new(200)
+                .body(form)
+                .set_mime(mime::APPLICATION_WWW_FORM_URLENCODED),
+            Err(e) => {
+                let message = format!("cannot serialize form: {}", e);
+                Error::new(StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+            }
+        }
+    }
+}
+
+/// An HTML response body.
+///
+/// ```
+/// use tide::Html;
+///
+/// let mut app = tide::new();
+/// app.at("/").get(|_| async { Html("<h1>Hello, world!</h1>") });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Html<S>(pub S);
+
+/// Respond with the string as `text/html; charset=utf-8`.
+impl<S: Into<String> + Send> IntoResponse for Html<S> {
+    fn into_response(self) -> Response {
+        Response::new(200)
+            .body_string(self.0.into())
+            .set_mime(mime::TEXT_HTML_UTF_8)
+    }
+}
+
+/// A binary response body, such as a `Vec<u8>`, a `&'static [u8]` or a `bytes::Bytes`.
+///
+/// As an extractor, `Bytes` reads the whole request body.
+///
+/// ```
+/// use tide::Bytes;
+///
+/// let mut app = tide::new();
+/// app.at("/pixel.gif").get(|_| async { Bytes(&b"GIF89a"[..]) });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bytes<B = Vec<u8>>(pub B);
+
+/// Respond with the bytes as `application/octet-stream`.
+impl<B: Into<Body> + Send> IntoResponse for Bytes<B> {
+    fn into_response(self) -> Response {
+        Response::new(200).body(self.0)
+    }
+}
+
+/// Respond with the `Ok` or the `Err` value.
+///
+/// An `Err` value turning into a success response is a bug of the endpoint: it is logged, and
+/// answered with `500 Internal Server Error` instead, keeping the cause of the response.
 impl<T: IntoResponse, U: IntoResponse> IntoResponse for Result<T, U> {
     fn into_response(self) -> Response {
         match self {
             Ok(r) => r.into_response(),
             Err(r) => {
-                let res = r.into_response();
-                if res.status().is_success() {
-                    panic!(
-                        "Attempted to yield error response with success code {:?}",
-                        res.status()
-                    )
+                let mut res = r.into_response();
+                if !res.status().is_success() {
+                    return res;
+                }
+                log::error!(
+                    "error response with success status {}, answering with 500",
+                    res.status()
+                );
+                let mut coerced = Response::new(StatusCode::INTERNAL_SERVER_ERROR.as_u16());
+                if let Some(cause) = res.take_cause() {
+                    coerced = coerced.set_cause_box(cause);
+                }
+                if let Some(backtrace) = res.take_backtrace() {
+                    coerced = coerced.set_backtrace(backtrace);
                 }
-                res
+                coerced
             }
         }
     }
 }
 
+/// Respond with the `Some` value, or with `404 Not Found` for `None`.
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    fn into_response(self) -> Response {
+        match self {
+            Some(r) => r.into_response(),
+            None => Response::new(StatusCode::NOT_FOUND.as_u16()),
+        }
+    }
+}
+
 impl IntoResponse for Response {
     fn into_response(self) -> Response {
         self
@@ -1,19 +1,68 @@
+use bytes::BytesMut;
 use cookie::Cookie;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use hyper::header::{self, HeaderName, HeaderValue};
 use hyper::StatusCode;
 use hyper::Body;
 use mime::Mime;
+use percent_encoding::utf8_percent_encode;
 use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
-pub use into_response::IntoResponse;
+use std::backtrace::Backtrace;
+use std::convert::TryInto;
+use std::io;
+use std::sync::Arc;
 
+use crate::error::{self, Cause, CauseBacktrace};
+use crate::Error;
+
+pub use headers::{CacheControl, ContentDisposition};
+pub use into_response::{Bytes, Html, IntoResponse};
+
+mod headers;
 mod into_response;
 
+/// The size of the chunks readers are streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The type of newline-delimited JSON bodies.
+const NDJSON: &str = "application/x-ndjson";
+
 #[derive(Debug)]
 pub(crate) enum CookieEvent {
     Added(Cookie<'static>),
     Removed(Cookie<'static>),
 }
 
+/// Convert a header name and value, for the `try_*_header` methods.
+fn header_pair<K, V>(key: K, value: V) -> Result<(HeaderName, HeaderValue), Error>
+where
+    K: TryInto<HeaderName>,
+    K::Error: Into<hyper::http::Error>,
+    V: TryInto<HeaderValue>,
+    V::Error: Into<hyper::http::Error>,
+{
+    let key = key.try_into().map_err(Into::<hyper::http::Error>::into)?;
+    let value = value.try_into().map_err(Into::<hyper::http::Error>::into)?;
+    Ok((key, value))
+}
+
+/// Capitalize each word of a header name, as in `Accept-Encoding`.
+fn title_case(name: &str) -> String {
+    let words: Vec<String> = name
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    words.join("-")
+}
+
 /// An HTTP response
 #[derive(Debug)]
 pub struct Response {
@@ -52,6 +101,116 @@ impl Response {
         }
     }
 
+    /// Create a new instance streaming the chunks of `stream` as `application/octet-stream`.
+    ///
+    /// Chunks are only pulled from the stream when the client is ready to receive them. `len`
+    /// is sent as the `Content-Length` if known, and must then match the length of the body,
+    /// which is otherwise sent chunked. An error ends the body early, aborting the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::stream;
+    /// use tide::Response;
+    ///
+    /// let chunks = vec![Ok::<_, std::io::Error>("hello, "), Ok("world")];
+    /// let res = Response::from_stream(200, stream::iter(chunks), Some(12));
+    /// assert_eq!(res.headers()["Content-Length"], "12");
+    /// ```
+    pub fn from_stream<S, O, E>(status: u16, stream: S, len: Option<u64>) -> Self
+    where
+        S: Stream<Item = Result<O, E>> + Send + 'static,
+        O: Into<bytes::Bytes> + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        let res = Self::with_reader(status, Body::wrap_stream(stream.map_ok(Into::into)))
+            .set_mime(mime::APPLICATION_OCTET_STREAM);
+        match len {
+            Some(len) => res.set_header("Content-Length", len.to_string()),
+            None => res,
+        }
+    }
+
+    /// Create a new instance streaming the contents of `reader` as `application/octet-stream`.
+    ///
+    /// The reader is read in chunks, and only when the client is ready to receive them. `len`
+    /// is sent as the `Content-Length` if known, as for [`from_stream`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::{Request, Response};
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/backup").get(|_: Request<()>| async move {
+    ///     let file = tokio::fs::File::open("backup.tar").await?;
+    ///     let len = file.metadata().await?.len();
+    ///     Ok::<_, tide::Error>(Response::from_async_read(200, file, Some(len)))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    ///
+    /// [`from_stream`]: #method.from_stream
+    pub fn from_async_read<R>(status: u16, reader: R, len: Option<u64>) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let state = (Box::pin(reader), BytesMut::new());
+        let chunks = stream::try_unfold(state, |(mut reader, mut buf)| async move {
+            if buf.capacity() < CHUNK_SIZE / 4 {
+                buf.reserve(CHUNK_SIZE);
+            }
+            match reader.read_buf(&mut buf).await? {
+                0 => Ok::<_, io::Error>(None),
+                _ => Ok(Some((buf.split().freeze(), (reader, buf)))),
+            }
+        });
+        Self::from_stream(status, chunks, len)
+    }
+
+    /// Create a new instance streaming the items of `stream` as newline-delimited JSON, with
+    /// the `application/x-ndjson` type.
+    ///
+    /// Each item is serialized when the client is ready to receive it, and sent on its own
+    /// line. An item failing to serialize is logged, and aborts the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::stream;
+    /// use serde::Serialize;
+    /// use tide::Response;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Event {
+    ///     id: u32,
+    /// }
+    ///
+    /// let events = stream::iter((0..3).map(|id| Event { id }));
+    /// let res = Response::ndjson(200, events);
+    /// assert_eq!(res.headers()["Content-Type"], "application/x-ndjson");
+    /// ```
+    pub fn ndjson<S, T>(status: u16, stream: S) -> Self
+    where
+        S: Stream<Item = T> + Send + 'static,
+        T: Serialize,
+    {
+        let lines = stream.map(|item| {
+            let mut line = serde_json::to_vec(&item).map_err(|e| {
+                log::error!("failed to serialize a JSON line: {}", e);
+                e
+            })?;
+            line.push(b'\n');
+            Ok::<_, serde_json::Error>(line)
+        });
+        Self::from_stream(status, lines, None).set_mime(NDJSON.parse().unwrap())
+    }
+
     /// Returns the statuscode.
     pub fn status(&self) -> hyper::StatusCode {
         self.res.status()
@@ -63,11 +222,73 @@ impl Response {
         self
     }
 
+    /// The error this response was created from, if any.
+    ///
+    /// Responses created from an [`Error`] keep the error they stand for, for middleware to
+    /// log or inspect.
+    ///
+    /// [`Error`]: error/struct.Error.html
+    pub fn cause(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.res
+            .extensions()
+            .get::<Cause>()
+            .map(|cause| &*cause.0 as _)
+    }
+
+    /// Attach the error this response was created from.
+    pub(crate) fn set_cause_box(
+        mut self,
+        cause: Box<dyn std::error::Error + Send + Sync + 'static>,
+    ) -> Self {
+        self.res.extensions_mut().insert(Cause(cause));
+        self
+    }
+
+    /// Take the error this response was created from.
+    pub(crate) fn take_cause(
+        &mut self,
+    ) -> Option<Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.res.extensions_mut().remove::<Cause>().map(|cause| cause.0)
+    }
+
+    /// The backtrace of the error this response was created from, if it was captured.
+    ///
+    /// See [`Error::backtrace`].
+    ///
+    /// [`Error::backtrace`]: error/struct.Error.html#method.backtrace
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.res
+            .extensions()
+            .get::<CauseBacktrace>()
+            .and_then(|backtrace| error::captured(&backtrace.0))
+    }
+
+    /// Attach the backtrace of the error this response was created from.
+    pub(crate) fn set_backtrace(mut self, backtrace: Arc<Backtrace>) -> Self {
+        self.res.extensions_mut().insert(CauseBacktrace(backtrace));
+        self
+    }
+
+    /// Take the backtrace of the error this response was created from.
+    pub(crate) fn take_backtrace(&mut self) -> Option<Arc<Backtrace>> {
+        self.res
+            .extensions_mut()
+            .remove::<CauseBacktrace>()
+            .map(|backtrace| backtrace.0)
+    }
+
     pub(crate) fn response_mut(&mut self) -> &mut hyper::Response<Body> {
         &mut self.res
     }
 
     /// Insert an HTTP header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not a valid header name, or if `value` is not a valid header value,
+    /// for example if it contains a newline. See [`try_set_header`] for a fallible version.
+    ///
+    /// [`try_set_header`]: #method.try_set_header
     pub fn set_header(mut self, key: &'static str, value: impl AsRef<str>) -> Self {
         let value = value.as_ref().to_owned();
         self.res.headers_mut().insert(key, value.parse().unwrap());
@@ -75,12 +296,160 @@ impl Response {
     }
 
     /// Append an HTTP header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not a valid header name, or if `value` is not a valid header value,
+    /// for example if it contains a newline. See [`try_append_header`] for a fallible version.
+    ///
+    /// [`try_append_header`]: #method.try_append_header
     pub fn append_header(mut self, key: &'static str, value: impl AsRef<str>) -> Self {
         let value = value.as_ref().to_owned();
         self.res.headers_mut().append(key, value.parse().unwrap());
         self
     }
 
+    /// Insert an HTTP header, replacing the values of the header, if any.
+    ///
+    /// The name and value can be given as a [`HeaderName`] and a [`HeaderValue`], or as
+    /// strings to parse.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `500 Internal Server Error` if `key` is not a valid header name, or if
+    /// `value` is not a valid header value, for example if it contains a newline.
+    ///
+    /// ```
+    /// # fn main() -> tide::Result<()> {
+    /// use tide::Response;
+    ///
+    /// let res = Response::new(200).try_set_header("X-Request-Id", "a1b2")?;
+    /// assert!(Response::new(200).try_set_header("X-Name", "a\nb").is_err());
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// [`HeaderName`]: http/header/struct.HeaderName.html
+    /// [`HeaderValue`]: http/header/struct.HeaderValue.html
+    pub fn try_set_header<K, V>(mut self, key: K, value: V) -> Result<Self, Error>
+    where
+        K: TryInto<HeaderName>,
+        K::Error: Into<hyper::http::Error>,
+        V: TryInto<HeaderValue>,
+        V::Error: Into<hyper::http::Error>,
+    {
+        let (key, value) = header_pair(key, value)?;
+        self.res.headers_mut().insert(key, value);
+        Ok(self)
+    }
+
+    /// Append an HTTP header, keeping the values of the header, if any.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`try_set_header`].
+    ///
+    /// [`try_set_header`]: #method.try_set_header
+    pub fn try_append_header<K, V>(mut self, key: K, value: V) -> Result<Self, Error>
+    where
+        K: TryInto<HeaderName>,
+        K::Error: Into<hyper::http::Error>,
+        V: TryInto<HeaderValue>,
+        V::Error: Into<hyper::http::Error>,
+    {
+        let (key, value) = header_pair(key, value)?;
+        self.res.headers_mut().append(key, value);
+        Ok(self)
+    }
+
+    /// Set a typed HTTP header, implementing the [`headers::Header`] trait.
+    ///
+    /// The header replaces the values of the header with the same name, if any.
+    ///
+    /// ```
+    /// use headers::ETag;
+    /// use tide::Response;
+    ///
+    /// let etag: ETag = "\"v1\"".parse().unwrap();
+    /// let res = Response::new(200).set_typed_header(etag);
+    /// assert_eq!(res.headers()["ETag"], "\"v1\"");
+    /// ```
+    ///
+    /// [`headers::Header`]: https://docs.rs/headers/0.3/headers/trait.Header.html
+    #[cfg(feature = "headers")]
+    #[cfg_attr(feature = "docs", doc(cfg(headers)))]
+    pub fn set_typed_header<H: ::headers::Header>(mut self, header: H) -> Self {
+        use ::headers::HeaderMapExt;
+
+        self.res.headers_mut().typed_insert(header);
+        self
+    }
+
+    /// Set the `Cache-Control` header.
+    pub fn set_cache_control(mut self, cache_control: CacheControl) -> Self {
+        let value = HeaderValue::from_str(&cache_control.to_string())
+            .expect("cache directives are valid header values");
+        self.res.headers_mut().insert(header::CACHE_CONTROL, value);
+        self
+    }
+
+    /// Set the `Content-Disposition` header.
+    pub fn set_content_disposition(mut self, disposition: ContentDisposition) -> Self {
+        let value = HeaderValue::from_str(&disposition.to_string())
+            .expect("escaped dispositions are valid header values");
+        self.res
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, value);
+        self
+    }
+
+    /// Set the `Location` header.
+    ///
+    /// Characters that are not allowed in URIs, such as spaces or non-ASCII characters, are
+    /// percent-encoded.
+    pub fn set_location(mut self, location: impl AsRef<str>) -> Self {
+        let location = utf8_percent_encode(location.as_ref(), headers::LOCATION).to_string();
+        let value =
+            HeaderValue::from_str(&location).expect("encoded URIs are valid header values");
+        self.res.headers_mut().insert(header::LOCATION, value);
+        self
+    }
+
+    /// Add `name` to the `Vary` header, unless it is already listed.
+    ///
+    /// Names are listed in their usual case, such as `Accept-Encoding`.
+    ///
+    /// ```
+    /// use tide::http::header::{ACCEPT, ACCEPT_ENCODING};
+    /// use tide::Response;
+    ///
+    /// let res = Response::new(200)
+    ///     .append_vary(ACCEPT)
+    ///     .append_vary(ACCEPT_ENCODING)
+    ///     .append_vary(ACCEPT);
+    /// assert_eq!(res.headers()["Vary"], "Accept, Accept-Encoding");
+    /// ```
+    pub fn append_vary(mut self, name: HeaderName) -> Self {
+        let headers = self.res.headers_mut();
+        let mut names: Vec<String> = headers
+            .get_all(header::VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let listed = names
+            .iter()
+            .any(|listed| listed == "*" || listed.eq_ignore_ascii_case(name.as_str()));
+        if !listed {
+            names.push(title_case(name.as_str()));
+            let value = HeaderValue::from_str(&names.join(", "))
+                .expect("header names are valid header values");
+            headers.insert(header::VARY, value);
+        }
+        self
+    }
+
     /// Set the request MIME.
     ///
     /// [Read more on MDN](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
@@ -145,25 +514,6 @@ impl Response {
         Ok(self.set_mime(mime::APPLICATION_JSON))
     }
 
-    // fn body_multipart(&mut self) -> BoxTryFuture<Multipart<Cursor<Vec<u8>>>> {
-    //     const BOUNDARY: &str = "boundary=";
-    //     let boundary = self.headers().get("content-type").and_then(|ct| {
-    //         let ct = ct.to_str().ok()?;
-    //         let idx = ct.find(BOUNDARY)?;
-    //         Some(ct[idx + BOUNDARY.len()..].to_string())
-    //     });
-
-    //     let body = self.take_body();
-
-    //     Box::pin(async move {
-    //         let body = body.into_vec().await.client_err()?;
-    //         let boundary = boundary
-    //             .ok_or_else(|| StringError(format!("no boundary found")))
-    //             .client_err()?;
-    //         Ok(Multipart::with_body(Cursor::new(body), boundary))
-    //     })
-    // }
-
     /// Add cookie to the cookie jar.
     pub fn set_cookie(&mut self, cookie: Cookie<'static>) {
         self.cookie_events.push(CookieEvent::Added(cookie));
@@ -177,9 +527,9 @@ impl Response {
 }
 
 #[doc(hidden)]
-impl Into<hyper::Response<Body>> for Response {
-    fn into(self) -> hyper::Response<Body> {
-        self.res
+impl From<Response> for hyper::Response<Body> {
+    fn from(res: Response) -> Self {
+        res.res
     }
 }
 
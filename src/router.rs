@@ -1,7 +1,9 @@
 use route_recognizer::{Match, Params, Router as MethodRouter};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::endpoint::DynEndpoint;
+use crate::guard::{Guard, Guarded};
 use crate::utils::BoxFuture;
 use crate::{Request, Response};
 use hyper::{Method, StatusCode};
@@ -10,16 +12,25 @@ use hyper::{Method, StatusCode};
 ///
 /// Internally, we have a separate state machine per http method; indexing
 /// by the method first allows the table itself to be more efficient.
+///
+/// The state machines map to indices into `endpoints`, so that endpoints
+/// registered later for the same path and method can be added to the
+/// existing entry instead of replacing it.
 #[allow(missing_debug_implementations)]
 pub(crate) struct Router<State> {
-    method_map: HashMap<Method, MethodRouter<Box<DynEndpoint<State>>>>,
-    all_method_router: MethodRouter<Box<DynEndpoint<State>>>,
+    method_map: HashMap<Method, MethodRouter<usize>>,
+    all_method_router: MethodRouter<usize>,
+    endpoints: Vec<(String, Guarded<State>)>,
+    indices: HashMap<(Option<Method>, String), usize>,
+    fallback_router: MethodRouter<(String, Box<DynEndpoint<State>>)>,
 }
 
 /// The result of routing a URL
 pub(crate) struct Selection<'a, State> {
     pub(crate) endpoint: &'a DynEndpoint<State>,
     pub(crate) params: Params,
+    /// The path pattern of the matched route, if any.
+    pub(crate) route: Option<&'a str>,
 }
 
 impl<State: 'static> Router<State> {
@@ -27,18 +38,64 @@ impl<State: 'static> Router<State> {
         Router {
             method_map: HashMap::default(),
             all_method_router: MethodRouter::new(),
+            endpoints: Vec::new(),
+            indices: HashMap::default(),
+            fallback_router: MethodRouter::new(),
+        }
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        path: &str,
+        method: Method,
+        guards: Vec<Arc<dyn Guard<State>>>,
+        ep: Box<DynEndpoint<State>>,
+    ) {
+        if let Some(index) = self.insert(path, Some(method.clone()), guards, ep) {
+            self.method_map
+                .entry(method)
+                .or_insert_with(MethodRouter::new)
+                .add(path, index)
         }
     }
 
-    pub(crate) fn add(&mut self, path: &str, method: Method, ep: Box<DynEndpoint<State>>) {
-        self.method_map
-            .entry(method)
-            .or_insert_with(MethodRouter::new)
-            .add(path, ep)
+    pub(crate) fn add_all(
+        &mut self,
+        path: &str,
+        guards: Vec<Arc<dyn Guard<State>>>,
+        ep: Box<DynEndpoint<State>>,
+    ) {
+        if let Some(index) = self.insert(path, None, guards, ep) {
+            self.all_method_router.add(path, index)
+        }
     }
 
-    pub(crate) fn add_all(&mut self, path: &str, ep: Box<DynEndpoint<State>>) {
-        self.all_method_router.add(path, ep)
+    /// Add an endpoint that handles requests to `path` which would otherwise
+    /// result in a `404 Not Found`.
+    pub(crate) fn add_fallback(&mut self, path: &str, ep: Box<DynEndpoint<State>>) {
+        self.fallback_router.add(path, (path.to_owned(), ep))
+    }
+
+    /// Add the endpoint to the entry for `path` and `method`, returning the
+    /// index of the entry if it did not exist yet.
+    fn insert(
+        &mut self,
+        path: &str,
+        method: Option<Method>,
+        guards: Vec<Arc<dyn Guard<State>>>,
+        ep: Box<DynEndpoint<State>>,
+    ) -> Option<usize> {
+        let key = (method, path.to_owned());
+        if let Some(&index) = self.indices.get(&key) {
+            self.endpoints[index].1.add(guards, ep);
+            return None;
+        }
+        let mut entry = Guarded::new();
+        entry.add(guards, ep);
+        self.endpoints.push((path.to_owned(), entry));
+        let index = self.endpoints.len() - 1;
+        self.indices.insert(key, index);
+        Some(index)
     }
 
     pub(crate) fn route(&self, path: &str, method: Method) -> Selection<'_, State> {
@@ -47,14 +104,18 @@ impl<State: 'static> Router<State> {
             .get(&method)
             .and_then(|r| r.recognize(path).ok())
         {
+            let (route, endpoint) = &self.endpoints[*handler];
             Selection {
-                endpoint: &**handler,
+                endpoint,
                 params,
+                route: Some(route),
             }
         } else if let Ok(Match { handler, params }) = self.all_method_router.recognize(path) {
+            let (route, endpoint) = &self.endpoints[*handler];
             Selection {
-                endpoint: &**handler,
+                endpoint,
                 params,
+                route: Some(route),
             }
         } else if method == Method::HEAD {
             // If it is a HTTP HEAD request then check if there is a callback in the endpoints map
@@ -72,11 +133,20 @@ impl<State: 'static> Router<State> {
             Selection {
                 endpoint: &method_not_allowed,
                 params: Params::new(),
+                route: None,
+            }
+        } else if let Ok(Match { handler, params }) = self.fallback_router.recognize(path) {
+            let (route, endpoint) = handler;
+            Selection {
+                endpoint: &**endpoint,
+                params,
+                route: Some(route),
             }
         } else {
             Selection {
                 endpoint: &not_found_endpoint,
                 params: Params::new(),
+                route: None,
             }
         }
     }
@@ -0,0 +1,165 @@
+use hyper::body::HttpBody;
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, HeaderMap, Method, StatusCode, Uri, Version};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::endpoint::DynEndpoint;
+use crate::{Endpoint, Error, IntoResponse, Problem, Request, Response};
+
+/// A description of the request an error occurred in, given to [`Server::on_error`] handlers.
+///
+/// [`Server::on_error`]: struct.Server.html#method.on_error
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+}
+
+impl RequestInfo {
+    pub(crate) fn new(req: &hyper::Request<Body>) -> Self {
+        RequestInfo {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            version: req.version(),
+            headers: req.headers().clone(),
+        }
+    }
+
+    /// The HTTP method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The URI of the request.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The HTTP version of the request.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The headers of the request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+type ErrorHandler = dyn Fn(Error, &RequestInfo) -> Response + Send + Sync + 'static;
+
+/// The error handler and error pages of a server.
+pub(crate) struct ErrorHandlers<State> {
+    on_error: Option<Arc<ErrorHandler>>,
+    pages: HashMap<StatusCode, Arc<DynEndpoint<State>>>,
+    problems: bool,
+}
+
+impl<State> Clone for ErrorHandlers<State> {
+    fn clone(&self) -> Self {
+        ErrorHandlers {
+            on_error: self.on_error.clone(),
+            pages: self.pages.clone(),
+            problems: self.problems,
+        }
+    }
+}
+
+impl<State: Send + Sync + 'static> ErrorHandlers<State> {
+    pub(crate) fn new() -> Self {
+        ErrorHandlers {
+            on_error: None,
+            pages: HashMap::new(),
+            problems: false,
+        }
+    }
+
+    pub(crate) fn set_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(Error, &RequestInfo) -> Response + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(handler));
+    }
+
+    pub(crate) fn add_page(&mut self, status: StatusCode, ep: impl Endpoint<State>) {
+        self.pages.insert(status, Arc::new(ep));
+    }
+
+    pub(crate) fn set_problems(&mut self, enabled: bool) {
+        self.problems = enabled;
+    }
+
+    /// Whether errors need to be handled at all.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.on_error.is_none() && self.pages.is_empty() && !self.problems
+    }
+
+    /// Render `res` if it is an error response.
+    ///
+    /// The error page registered for its status takes precedence over the error handler, which
+    /// takes precedence over rendering problems.
+    pub(crate) async fn render(
+        &self,
+        mut res: Response,
+        info: RequestInfo,
+        state: Arc<State>,
+    ) -> Response {
+        let status = res.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return res;
+        }
+        if let Some(page) = self.pages.get(&status) {
+            let mut req = hyper::Request::new(Body::empty());
+            *req.method_mut() = info.method;
+            *req.uri_mut() = info.uri;
+            *req.version_mut() = info.version;
+            *req.headers_mut() = info.headers;
+            let cause = res.take_cause();
+            let mut page = page.call(Request::new(state, req, Vec::new())).await;
+            copy_headers(res.headers(), &mut page);
+            if let Some(cause) = cause {
+                page = page.set_cause_box(cause);
+            }
+            return page.set_status(status);
+        }
+        match &self.on_error {
+            Some(handler) => handler(Error::from(res), &info),
+            None if self.problems && is_plain_error(&mut res) => render_problem(res, &info),
+            None => res,
+        }
+    }
+}
+
+/// Whether `res` stands for an error without describing it, which is the case of responses
+/// created from an `Error`, and of empty ones like the `404` and `405` of the router.
+pub(crate) fn is_plain_error(res: &mut Response) -> bool {
+    let is_problem = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/problem+json"));
+    !is_problem && (res.cause().is_some() || res.response_mut().body().is_end_stream())
+}
+
+/// Replace an error response with a problem document, keeping its headers and cause.
+fn render_problem(res: Response, info: &RequestInfo) -> Response {
+    let headers = res.headers().clone();
+    let err = Error::from(res);
+    let problem = Problem::from(&err).set_instance(info.uri().path());
+    let mut res = problem.into_response();
+    copy_headers(&headers, &mut res);
+    res.set_cause_box(err.into_inner())
+}
+
+/// Copy the headers of an error response onto the response replacing it, such as the `Allow`
+/// of a `405` or the `WWW-Authenticate` of a `401`, except the ones describing the body.
+pub(crate) fn copy_headers(headers: &HeaderMap, res: &mut Response) {
+    for (name, value) in headers.iter() {
+        if name != CONTENT_TYPE && name != CONTENT_LENGTH {
+            res.response_mut().headers_mut().append(name, value.clone());
+        }
+    }
+}
@@ -1,18 +1,26 @@
 //! An HTTP server
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
+use crate::request::{BodyLimit, MatchedRoute};
 use crate::utils::BoxFuture;
 use crate::{
     middleware::{Middleware, Next},
     router::{Router, Selection},
-    Endpoint, Request, Response,
+    Endpoint, Error, Request, Response,
 };
 
+pub(crate) mod errors;
 mod route;
+mod scope;
 
+pub use errors::RequestInfo;
 pub use route::Route;
-use hyper::Body;
+pub use scope::Scope;
+
+use errors::ErrorHandlers;
+use hyper::{Body, StatusCode};
 use std::convert::Infallible;
 use hyper::service::{make_service_fn, service_fn};
 use std::net::ToSocketAddrs;
@@ -22,16 +30,16 @@ use std::net::ToSocketAddrs;
 /// Servers are built up as a combination of *state*, *endpoints* and *middleware*:
 ///
 /// - Server state is user-defined, and is provided via the [`Server::with_state`] function. The
-/// state is available as a shared reference to all app endpoints.
+///   state is available as a shared reference to all app endpoints.
 ///
 /// - Endpoints provide the actual application-level code corresponding to
-/// particular URLs. The [`Server::at`] method creates a new *route* (using
-/// standard router syntax), which can then be used to register endpoints
-/// for particular HTTP request types.
+///   particular URLs. The [`Server::at`] method creates a new *route* (using
+///   standard router syntax), which can then be used to register endpoints
+///   for particular HTTP request types.
 ///
 /// - Middleware extends the base Tide framework with additional request or
-/// response processing, such as compression, default headers, or logging. To
-/// add middleware to an app, use the [`Server::middleware`] method.
+///   response processing, such as compression, default headers, or logging. To
+///   add middleware to an app, use the [`Server::middleware`] method.
 /////
 ///// # Hello, world!
 /////
@@ -135,6 +143,47 @@ pub struct Server<State> {
     router: Router<State>,
     middleware: Vec<Arc<dyn Middleware<State>>>,
     state: State,
+    body_limit: Option<u64>,
+    errors: ErrorHandlers<State>,
+    routes: Arc<SharedRoutes<State>>,
+}
+
+/// The routes and middleware of a running server.
+///
+/// Requests take a snapshot of the current table, so swapping it through a
+/// [`ReloadHandle`] does not affect requests that are already in flight.
+struct Routes<State> {
+    router: Router<State>,
+    middleware: Vec<Arc<dyn Middleware<State>>>,
+    body_limit: Option<u64>,
+    errors: ErrorHandlers<State>,
+}
+
+/// The routes of a server, shared with its reload handles.
+struct SharedRoutes<State> {
+    current: RwLock<Arc<Routes<State>>>,
+    /// Whether routes were swapped in before the server was turned into a
+    /// service, which then keeps them instead of installing its own.
+    swapped: AtomicBool,
+}
+
+impl<State> SharedRoutes<State> {
+    fn load(&self) -> Arc<Routes<State>> {
+        self.current.read().unwrap().clone()
+    }
+
+    fn swap(&self, routes: Routes<State>) {
+        let mut current = self.current.write().unwrap();
+        *current = Arc::new(routes);
+        self.swapped.store(true, Ordering::Relaxed);
+    }
+
+    fn install(&self, routes: Routes<State>) {
+        let mut current = self.current.write().unwrap();
+        if !self.swapped.load(Ordering::Relaxed) {
+            *current = Arc::new(routes);
+        }
+    }
 }
 
 impl Server<()> {
@@ -202,6 +251,17 @@ impl<State: Send + Sync + 'static> Server<State> {
                 crate::middleware::cookies::CookiesMiddleware::new(),
             )],
             state,
+            body_limit: None,
+            errors: ErrorHandlers::new(),
+            routes: Arc::new(SharedRoutes {
+                current: RwLock::new(Arc::new(Routes {
+                    router: Router::new(),
+                    middleware: Vec::new(),
+                    body_limit: None,
+                    errors: ErrorHandlers::new(),
+                })),
+                swapped: AtomicBool::new(false),
+            }),
         }
     }
 
@@ -255,6 +315,27 @@ impl<State: Send + Sync + 'static> Server<State> {
         Route::new(&mut self.router, path.to_owned())
     }
 
+    /// Add a group of routes sharing the given path `prefix`.
+    ///
+    /// The closure receives a [`Scope`], which adds routes relative to the
+    /// prefix and can carry its own middleware, metadata and fallback
+    /// endpoint.
+    ///
+    /// ```rust,no_run
+    /// # let mut app = tide::Server::new();
+    /// app.scope("/api/v1", |api| {
+    ///     api.at("/users").get(|_| async move { "users" });
+    ///     api.at("/posts").get(|_| async move { "posts" });
+    /// });
+    /// ```
+    pub fn scope<F>(&mut self, prefix: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Scope<'_, State>),
+    {
+        f(&mut Scope::new(&mut self.router, prefix.to_owned()));
+        self
+    }
+
     /// Add middleware to an application.
     ///
     /// Middleware provides application-global customization of the
@@ -270,15 +351,154 @@ impl<State: Send + Sync + 'static> Server<State> {
         self
     }
 
+    /// Limit the size of the request bodies read by the endpoints of this app.
+    ///
+    /// Reading a larger body with one of the `body_*` methods of [`Request`] fails with
+    /// `413 Payload Too Large`, before anything is read if the `Content-Length` of the body is
+    /// too large already. Bodies are unlimited by default. [`Route::body_limit`] and
+    /// [`Request::set_body_limit`] override the limit for a route or a single read.
+    ///
+    /// ```rust,no_run
+    /// # let mut app = tide::Server::new();
+    /// app.body_limit(1024 * 1024);
+    /// ```
+    ///
+    /// [`Request`]: struct.Request.html
+    /// [`Route::body_limit`]: struct.Route.html#method.body_limit
+    /// [`Request::set_body_limit`]: struct.Request.html#method.set_body_limit
+    pub fn body_limit(&mut self, limit: u64) -> &mut Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// Render the error responses of this app with `handler`.
+    ///
+    /// Every response with a `4xx` or `5xx` status goes through the handler, whether it comes
+    /// from an [`Error`] returned by an endpoint, from middleware, or from the router when no
+    /// route matches. The handler gets the error, created from the response with
+    /// [`Error::from`], along with a description of the request, and returns the response to
+    /// send. Turning the error back into a response keeps the original one:
+    ///
+    /// ```rust,no_run
+    /// use tide::IntoResponse;
+    ///
+    /// let mut app = tide::new();
+    /// app.on_error(|err, req| {
+    ///     log::warn!("{} {}: {}", req.method(), req.uri(), err);
+    ///     err.into_response()
+    /// });
+    /// ```
+    ///
+    /// Statuses with an [error page] are rendered by the page instead.
+    ///
+    /// [`Error`]: error/struct.Error.html
+    /// [`Error::from`]: error/struct.Error.html#impl-From%3CResponse%3E
+    /// [error page]: #method.error_page
+    pub fn on_error<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(Error, &RequestInfo) -> Response + Send + Sync + 'static,
+    {
+        self.errors.set_handler(handler);
+        self
+    }
+
+    /// Render the error responses with the given `status` with `endpoint`.
+    ///
+    /// The endpoint gets a request with the method, URI and headers of the failed one, but
+    /// no body. Its response is sent with `status`, and keeps the [cause] and the headers of
+    /// the original response, such as the `Allow` of a `405`, except `Content-Type` and
+    /// `Content-Length`.
+    ///
+    /// ```rust,no_run
+    /// # let mut app = tide::new();
+    /// app.error_page(404, |_| async move {
+    ///     tide::Response::new(404)
+    ///         .body_string("<h1>Not found</h1>".to_string())
+    ///         .set_mime(mime::TEXT_HTML_UTF_8)
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid status code.
+    ///
+    /// [cause]: struct.Response.html#method.cause
+    pub fn error_page(&mut self, status: u16, endpoint: impl Endpoint<State>) -> &mut Self {
+        let status = StatusCode::from_u16(status).expect("invalid status code");
+        self.errors.add_page(status, endpoint);
+        self
+    }
+
+    /// Render errors as problem documents, as defined in RFC 7807.
+    ///
+    /// Responses created from an [`Error`], as well as empty `4xx` and `5xx` responses like
+    /// the `404` and `405` of the router, are replaced with the [`Problem`] describing them,
+    /// sent as `application/problem+json`. Other error responses, which already describe
+    /// the error in their own way, are left alone. [Error pages] and [error handlers] take
+    /// precedence over problems.
+    ///
+    /// ```rust,no_run
+    /// # let mut app = tide::new();
+    /// app.problem_details(true);
+    /// ```
+    ///
+    /// [`Error`]: error/struct.Error.html
+    /// [`Problem`]: struct.Problem.html
+    /// [Error pages]: #method.error_page
+    /// [error handlers]: #method.on_error
+    pub fn problem_details(&mut self, enabled: bool) -> &mut Self {
+        self.errors.set_problems(enabled);
+        self
+    }
+
+    /// Get a handle to replace the routes and middleware of this app while
+    /// it is running.
+    ///
+    /// [`ReloadHandle::swap`] atomically replaces the routes and middleware
+    /// of the running app. Routes swapped in before the app is served take
+    /// the place of the ones registered on the app itself.
+    ///
+    /// ```rust,no_run
+    /// # use futures::executor::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// let mut app = tide::new();
+    /// app.at("/").get(|_| async move { "Hello, world!" });
+    /// let handle = app.reload_handle();
+    ///
+    /// // Open the admin panel once the operator presses enter.
+    /// std::thread::spawn(move || {
+    ///     std::io::stdin().read_line(&mut String::new()).unwrap();
+    ///     let mut app = tide::new();
+    ///     app.at("/").get(|_| async move { "Hello, admin!" });
+    ///     app.at("/admin").get(|_| async move { "Admin panel" });
+    ///     handle.swap(app);
+    /// });
+    ///
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn reload_handle(&self) -> ReloadHandle<State> {
+        ReloadHandle {
+            routes: self.routes.clone(),
+        }
+    }
+
     /// Make this app into an `HttpService`.
     ///
     /// This lower-level method lets you host a Tide application within an HTTP
     /// server of your choice, via the `http_service` interface crate.
     pub fn into_http_service(self) -> Service<State> {
+        self.routes.install(Routes {
+            router: self.router,
+            middleware: self.middleware,
+            body_limit: self.body_limit,
+            errors: self.errors,
+        });
         Service {
-            router: Arc::new(self.router),
+            routes: self.routes,
             state: Arc::new(self.state),
-            middleware: Arc::new(self.middleware),
         }
     }
 
@@ -302,7 +522,7 @@ impl<State: Send + Sync + 'static> Server<State> {
             .serve(make_service)
             .await;
 
-        res.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        res.map_err(std::io::Error::other)?;
         Ok(())
     }
 }
@@ -313,21 +533,63 @@ impl<State: Send + Sync + 'static> Server<State> {
 /// i.e. for hosting a Tide app within some custom HTTP server.
 #[allow(missing_debug_implementations)]
 pub struct Service<State> {
-    router: Arc<Router<State>>,
+    routes: Arc<SharedRoutes<State>>,
     state: Arc<State>,
-    middleware: Arc<Vec<Arc<dyn Middleware<State>>>>,
 }
 
 impl<State> Clone for Service<State> {
     fn clone(&self) -> Self {
         Self {
-            router: self.router.clone(),
+            routes: self.routes.clone(),
             state: self.state.clone(),
-            middleware: self.middleware.clone(),
         }
     }
 }
 
+impl<State> Service<State> {
+    /// Get a handle to replace the routes and middleware of this service.
+    ///
+    /// See [`Server::reload_handle`] for details.
+    pub fn reload_handle(&self) -> ReloadHandle<State> {
+        ReloadHandle {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+/// A handle to replace the routes and middleware of a running server.
+///
+/// Created by [`Server::reload_handle`] or [`Service::reload_handle`].
+#[allow(missing_debug_implementations)]
+pub struct ReloadHandle<State> {
+    routes: Arc<SharedRoutes<State>>,
+}
+
+impl<State> Clone for ReloadHandle<State> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<State> ReloadHandle<State> {
+    /// Atomically replace the routes and middleware of the server with the
+    /// ones of `app`.
+    ///
+    /// Requests that are already being handled finish with the routes and
+    /// middleware they started with. The state of `app` is dropped; requests
+    /// keep seeing the state of the running server.
+    pub fn swap(&self, app: Server<State>) {
+        self.routes.swap(Routes {
+            router: app.router,
+            middleware: app.middleware,
+            body_limit: app.body_limit,
+            errors: app.errors,
+        });
+    }
+}
+
 impl<State: Sync + Send + 'static> Service<State> {
     async fn respond(self, req: hyper::Request<Body>) -> Result<hyper::Response<Body>, Infallible> {
         let req = Request::new(self.state.clone(), req, Vec::new());
@@ -341,27 +603,46 @@ impl<State: Sync + Send + 'static, InnerState: Sync + Send + 'static> Endpoint<S
 {
     fn call(&self, req: Request<State>) -> BoxFuture<'_, Response> {
         let Request {
-            request: req,
+            request: mut req,
             mut route_params,
             ..
         } = req;
         let path = req.uri().path().to_owned();
         let method = req.method().to_owned();
-        let router = self.router.clone();
-        let middleware = self.middleware.clone();
+        let routes = self.routes.load();
         let state = self.state.clone();
+        if let Some(limit) = routes.body_limit {
+            req.extensions_mut().insert(BodyLimit(limit));
+        }
 
         Box::pin(async move {
-            let Selection { endpoint, params } = router.route(&path, method);
+            let info = if routes.errors.is_empty() {
+                None
+            } else {
+                Some(RequestInfo::new(&req))
+            };
+            let Selection {
+                endpoint,
+                params,
+                route,
+            } = routes.router.route(&path, method);
             route_params.push(params);
-            let req = Request::new(state, req, route_params);
+            if let Some(route) = route {
+                let route = route.replace("*--tide-path-rest", "*");
+                req.extensions_mut().insert(MatchedRoute(route));
+            }
+            let req = Request::new(state.clone(), req, route_params);
 
             let next = Next {
                 endpoint,
-                next_middleware: &middleware,
+                next_middleware: &routes.middleware,
             };
 
-            next.run(req).await
+            let res = next.run(req).await;
+            match info {
+                Some(info) => routes.errors.render(res, info, state).await,
+                None => res,
+            }
         })
     }
 }
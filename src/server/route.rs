@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
 use crate::endpoint::MiddlewareEndpoint;
+use crate::fs::ServeDir;
+use crate::guard::Guard;
+use crate::request::BodyLimit;
 use crate::utils::BoxFuture;
 use crate::{router::Router, Endpoint, Middleware, Response};
 use hyper::{Method, Uri};
@@ -18,6 +21,7 @@ pub struct Route<'a, State> {
     router: &'a mut Router<State>,
     path: String,
     middleware: Vec<Arc<dyn Middleware<State>>>,
+    guards: Vec<Arc<dyn Guard<State>>>,
     /// Indicates whether the path of current route is treated as a prefix. Set by
     /// [`strip_prefix`].
     ///
@@ -31,26 +35,32 @@ impl<'a, State: 'static> Route<'a, State> {
             router,
             path,
             middleware: Vec::new(),
+            guards: Vec::new(),
             prefix: false,
         }
     }
 
-    /// Extend the route with the given `path`.
-    pub fn at<'b>(&'b mut self, path: &str) -> Route<'b, State> {
-        let mut p = self.path.clone();
-
-        if !p.ends_with('/') && !path.starts_with('/') {
-            p.push_str("/");
-        }
-
-        if path != "/" {
-            p.push_str(path);
+    pub(crate) fn with_middleware(
+        router: &'a mut Router<State>,
+        path: String,
+        middleware: Vec<Arc<dyn Middleware<State>>>,
+    ) -> Route<'a, State> {
+        Route {
+            router,
+            path,
+            middleware,
+            guards: Vec::new(),
+            prefix: false,
         }
+    }
 
+    /// Extend the route with the given `path`.
+    pub fn at<'b>(&'b mut self, path: &str) -> Route<'b, State> {
         Route {
-            router: &mut self.router,
-            path: p,
+            router: self.router,
+            path: join_path(&self.path, path),
             middleware: self.middleware.clone(),
+            guards: Vec::new(),
             prefix: false,
         }
     }
@@ -73,12 +83,56 @@ impl<'a, State: 'static> Route<'a, State> {
         self
     }
 
+    /// Limit the size of the request bodies read by the endpoints of the current route.
+    ///
+    /// The limit applies to the endpoints added afterwards and to routes created with
+    /// [`Route::at`], overriding the one of the server. See [`Request::set_body_limit`] for
+    /// details.
+    ///
+    /// ```no_run
+    /// # let mut app = tide::new();
+    /// app.at("/upload").body_limit(10 * 1024 * 1024).post(|_| async move { "" });
+    /// ```
+    ///
+    /// [`Route::at`]: #method.at
+    /// [`Request::set_body_limit`]: ../struct.Request.html#method.set_body_limit
+    pub fn body_limit(&mut self, limit: u64) -> &mut Self
+    where
+        State: Send + Sync,
+    {
+        self.middleware(BodyLimit(limit))
+    }
+
     /// Reset the middleware chain for the current route, if any.
     pub fn reset_middleware(&mut self) -> &mut Self {
         self.middleware.clear();
         self
     }
 
+    /// Guard the endpoints added to the current route.
+    ///
+    /// Endpoints added with guards do not replace endpoints already registered for the same path
+    /// and method. Instead, each request is handed to the first endpoint whose guards all accept
+    /// it, with endpoints added without guards tried last. If no endpoint accepts the request,
+    /// the response status is taken from the guard that rejected it, for example `415` for
+    /// [`guard::content_type`] or `406` for [`guard::accepts`].
+    ///
+    /// Guards are not inherited by routes created with [`Route::at`].
+    ///
+    /// [`guard::content_type`]: ../guard/fn.content_type.html
+    /// [`guard::accepts`]: ../guard/fn.accepts.html
+    /// [`Route::at`]: #method.at
+    pub fn guard(&mut self, guard: impl Guard<State>) -> &mut Self {
+        self.guards.push(Arc::new(guard));
+        self
+    }
+
+    /// Reset the guards for the current route, if any.
+    pub fn reset_guards(&mut self) -> &mut Self {
+        self.guards.clear();
+        self
+    }
+
     /// Nest a [`Server`] at the current path.
     ///
     /// [`Server`]: struct.Server.html
@@ -93,6 +147,46 @@ impl<'a, State: 'static> Route<'a, State> {
         self
     }
 
+    /// Serve the files of a directory at the current path.
+    ///
+    /// The directory's files are served below the current path, with `GET` and
+    /// `HEAD` requests to the path itself being answered with the directory's
+    /// index file. See [`ServeDir`] for the available options.
+    ///
+    /// ```no_run
+    /// # let mut app = tide::new();
+    /// app.at("/static").serve_dir("public/");
+    /// ```
+    ///
+    /// [`ServeDir`]: ../fs/struct.ServeDir.html
+    pub fn serve_dir(&mut self, dir: impl Into<ServeDir>) -> &mut Self
+    where
+        State: Send + Sync + 'static,
+    {
+        let dir = dir.into();
+        self.get(dir.clone());
+        // The wildcard needs at least one character, so the directory itself is registered
+        // both with and without a trailing slash.
+        if !self.path.ends_with('/') {
+            let path = format!("{}/", self.path);
+            self.sub_route(path).get(dir.clone());
+        }
+        let path = join_path(&self.path, "*--tide-path-rest");
+        self.sub_route(path).get(dir);
+        self
+    }
+
+    /// A route at `path` sharing the middleware and guards of this one.
+    fn sub_route(&mut self, path: String) -> Route<'_, State> {
+        Route {
+            router: self.router,
+            path,
+            middleware: self.middleware.clone(),
+            guards: self.guards.clone(),
+            prefix: false,
+        }
+    }
+
     /// Add an endpoint for the given HTTP method
     pub fn method(&mut self, method: Method, ep: impl Endpoint<State>) -> &mut Self {
         if self.prefix {
@@ -108,9 +202,11 @@ impl<'a, State: 'static> Route<'a, State> {
                     ));
                     (ep.clone(), ep)
                 };
-            self.router.add(&self.path, method.clone(), ep1);
+            self.router
+                .add(&self.path, method.clone(), self.guards.clone(), ep1);
+            let guards = self.guards.clone();
             let wildcard = self.at("*--tide-path-rest");
-            wildcard.router.add(&wildcard.path, method, ep2);
+            wildcard.router.add(&wildcard.path, method, guards, ep2);
         } else {
             let ep: Box<dyn Endpoint<_>> = if self.middleware.is_empty() {
                 Box::new(ep)
@@ -120,7 +216,7 @@ impl<'a, State: 'static> Route<'a, State> {
                     &self.middleware,
                 ))
             };
-            self.router.add(&self.path, method, ep);
+            self.router.add(&self.path, method, self.guards.clone(), ep);
         }
         self
     }
@@ -142,9 +238,10 @@ impl<'a, State: 'static> Route<'a, State> {
                     ));
                     (ep.clone(), ep)
                 };
-            self.router.add_all(&self.path, ep1);
+            self.router.add_all(&self.path, self.guards.clone(), ep1);
+            let guards = self.guards.clone();
             let wildcard = self.at("*--tide-path-rest");
-            wildcard.router.add_all(&wildcard.path, ep2);
+            wildcard.router.add_all(&wildcard.path, guards, ep2);
         } else {
             let ep: Box<dyn Endpoint<_>> = if self.middleware.is_empty() {
                 Box::new(ep)
@@ -154,7 +251,7 @@ impl<'a, State: 'static> Route<'a, State> {
                     &self.middleware,
                 ))
             };
-            self.router.add_all(&self.path, ep);
+            self.router.add_all(&self.path, self.guards.clone(), ep);
         }
         self
     }
@@ -214,6 +311,21 @@ impl<'a, State: 'static> Route<'a, State> {
     }
 }
 
+/// Append `path` to the route path `base`.
+pub(crate) fn join_path(base: &str, path: &str) -> String {
+    let mut p = base.to_owned();
+
+    if !p.ends_with('/') && !path.starts_with('/') {
+        p.push('/');
+    }
+
+    if path != "/" {
+        p.push_str(path);
+    }
+
+    p
+}
+
 #[derive(Debug)]
 struct StripPrefixEndpoint<E>(std::sync::Arc<E>);
 
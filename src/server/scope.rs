@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use crate::endpoint::MiddlewareEndpoint;
+use crate::request::BodyLimit;
+use crate::utils::BoxFuture;
+use crate::{router::Router, Endpoint, Middleware, Next, Request, Response};
+
+use super::route::{join_path, Route};
+
+/// A group of routes sharing a path prefix.
+///
+/// Scopes are created with [`Server::scope`] (or [`Scope::scope`] for nested groups). Middleware,
+/// metadata and fallback endpoints set on a scope apply to the routes in the group, but not to
+/// routes outside of it.
+///
+/// Middleware and metadata only apply to routes added *after* them, the same way middleware does
+/// for a [`Route`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use futures::executor::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// #[derive(Clone)]
+/// struct Version(u8);
+///
+/// let mut app = tide::new();
+/// app.scope("/api/v1", |api| {
+///     api.middleware(tide::middleware::RequestLogger::new());
+///     api.metadata(Version(1));
+///     api.at("/users").get(|req: tide::Request<()>| async move {
+///         format!("users, version {}", req.local::<Version>().unwrap().0)
+///     });
+///     api.fallback(|_| async move { tide::Response::new(404).body_string("no such API".into()) });
+/// });
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`Server::scope`]: struct.Server.html#method.scope
+/// [`Scope::scope`]: #method.scope
+/// [`Route`]: struct.Route.html
+#[allow(missing_debug_implementations)]
+pub struct Scope<'a, State> {
+    router: &'a mut Router<State>,
+    prefix: String,
+    middleware: Vec<Arc<dyn Middleware<State>>>,
+    metadata: Vec<Arc<Setter<State>>>,
+}
+
+type Setter<State> = dyn Fn(Request<State>) -> Request<State> + Send + Sync;
+
+impl<'a, State: Send + Sync + 'static> Scope<'a, State> {
+    pub(crate) fn new(router: &'a mut Router<State>, prefix: String) -> Scope<'a, State> {
+        Scope {
+            router,
+            prefix,
+            middleware: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Add a new route at the given `path`, relative to the scope's prefix.
+    pub fn at<'b>(&'b mut self, path: &str) -> Route<'b, State> {
+        let middleware = self.route_middleware();
+        Route::with_middleware(self.router, join_path(&self.prefix, path), middleware)
+    }
+
+    /// Add a nested scope at the given `prefix`, relative to this scope's prefix.
+    ///
+    /// The nested scope starts out with the middleware and metadata of this scope.
+    pub fn scope<F>(&mut self, prefix: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Scope<'_, State>),
+    {
+        let mut scope = Scope {
+            router: self.router,
+            prefix: join_path(&self.prefix, prefix),
+            middleware: self.middleware.clone(),
+            metadata: self.metadata.clone(),
+        };
+        f(&mut scope);
+        self
+    }
+
+    /// Apply the given middleware to the routes added to this scope from now on.
+    pub fn middleware(&mut self, middleware: impl Middleware<State>) -> &mut Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Limit the size of the request bodies read by the routes added to this scope from now on.
+    ///
+    /// See [`Route::body_limit`] for details.
+    ///
+    /// [`Route::body_limit`]: struct.Route.html#method.body_limit
+    pub fn body_limit(&mut self, limit: u64) -> &mut Self
+    where
+        State: Send + Sync,
+    {
+        self.middleware(BodyLimit(limit))
+    }
+
+    /// Attach a value to every request handled by this scope.
+    ///
+    /// The value is available through [`Request::local`], both to the scope's middleware and to
+    /// its endpoints. Like middleware, metadata applies to the routes added after it.
+    ///
+    /// [`Request::local`]: ../struct.Request.html#method.local
+    pub fn metadata<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.metadata
+            .push(Arc::new(move |req: Request<State>| req.set_local(value.clone())));
+        self
+    }
+
+    /// Handle requests below the scope's prefix that did not match any route.
+    ///
+    /// The fallback endpoint replaces the `404 Not Found` response the server would otherwise
+    /// send. Paths that exist with a different HTTP method still get a
+    /// `405 Method Not Allowed`. The scope's middleware applies to the fallback as well.
+    pub fn fallback(&mut self, ep: impl Endpoint<State>) -> &mut Self {
+        let middleware = self.route_middleware();
+        let ep = Arc::new(MiddlewareEndpoint::wrap_with_middleware(ep, &middleware));
+        self.router
+            .add_fallback(&self.prefix, Box::new(SharedEndpoint(ep.clone())));
+        self.router
+            .add_fallback(&join_path(&self.prefix, "*"), Box::new(SharedEndpoint(ep)));
+        self
+    }
+
+    fn route_middleware(&self) -> Vec<Arc<dyn Middleware<State>>> {
+        let mut middleware: Vec<Arc<dyn Middleware<State>>> = Vec::new();
+        if !self.metadata.is_empty() {
+            middleware.push(Arc::new(Metadata(self.metadata.clone())));
+        }
+        middleware.extend(self.middleware.iter().cloned());
+        middleware
+    }
+}
+
+/// Middleware inserting the metadata of a scope into the request.
+struct Metadata<State>(Vec<Arc<Setter<State>>>);
+
+impl<State: Send + Sync + 'static> Middleware<State> for Metadata<State> {
+    fn handle<'a>(&'a self, req: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        let req = self.0.iter().fold(req, |req, set| set(req));
+        next.run(req)
+    }
+}
+
+/// An endpoint registered at several paths.
+struct SharedEndpoint<E>(Arc<E>);
+
+impl<State, E: Endpoint<State>> Endpoint<State> for SharedEndpoint<E> {
+    fn call(&self, req: Request<State>) -> BoxFuture<'_, Response> {
+        self.0.call(req)
+    }
+}
@@ -4,3 +4,56 @@ use std::pin::Pin;
 /// An owned dynamically typed [`Future`] for use in cases where you can't
 /// statically type your result or need to add some indirection.
 pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A single entry of a comma-separated header value with quality parameters, such as
+/// `Accept` or `Accept-Encoding`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct QualityItem<'a> {
+    pub(crate) value: &'a str,
+    pub(crate) quality: f32,
+}
+
+/// Parse a header like `text/html;q=0.9, */*;q=0.1` into its entries.
+///
+/// Entries without a `q` parameter have a quality of `1.0`. Malformed quality values are
+/// treated as `0.0`, so that they never win a negotiation.
+pub(crate) fn parse_quality_list(header: &str) -> Vec<QualityItem<'_>> {
+    header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let value = parts.next()?.trim();
+            if value.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| {
+                    let (key, val) = param.split_at(param.find('=')?);
+                    if key.trim().eq_ignore_ascii_case("q") {
+                        Some(val[1..].trim().parse::<f32>().unwrap_or(0.0))
+                    } else {
+                        None
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+            Some(QualityItem { value, quality })
+        })
+        .collect()
+}
+
+/// Escape `s` for use in HTML text and attribute values.
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
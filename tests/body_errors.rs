@@ -0,0 +1,154 @@
+use bytes::Buf;
+use hyper::{body, Body};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tide::{Endpoint, Request, Response};
+
+#[derive(Debug, Deserialize)]
+struct Cat {
+    #[allow(dead_code)]
+    name: String,
+    #[allow(dead_code)]
+    lives: u8,
+}
+
+async fn post(path: &str, content_type: Option<&str>, body: &'static [u8]) -> Response {
+    let mut app = tide::new();
+    app.body_limit(64);
+    app.at("/string").post(|mut req: Request<()>| async move {
+        req.body_string().await?;
+        Ok::<_, tide::Error>("")
+    });
+    app.at("/json").post(|mut req: Request<()>| async move {
+        req.body_json::<Cat>().await?;
+        Ok::<_, tide::Error>("")
+    });
+    app.at("/form").post(|mut req: Request<()>| async move {
+        req.body_form::<Cat>().await?;
+        Ok::<_, tide::Error>("")
+    });
+    app.at("/multipart")
+        .post(|mut req: Request<()>| async move {
+            req.body_multipart()?;
+            Ok::<_, tide::Error>("")
+        });
+    let app = app.into_http_service();
+
+    let mut req = hyper::Request::builder().method("POST").uri(path);
+    if let Some(content_type) = content_type {
+        req = req.header("Content-Type", content_type);
+    }
+    let req = req.body(Body::from(body)).unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+async fn body_json(res: &mut Response) -> Value {
+    assert_eq!(res.headers()["Content-Type"], "application/json");
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn invalid_utf8() {
+    let mut res = post("/string", None, b"ab\xffcd").await;
+    assert_eq!(res.status(), 400);
+    let body = body_json(&mut res).await;
+    assert_eq!(body["error"], "invalid_utf8");
+    assert_eq!(body["valid_up_to"], 2);
+}
+
+#[tokio::test]
+async fn json_syntax_errors() {
+    let mut res = post("/json", Some("application/json"), b"{\n  \"name\": }").await;
+    assert_eq!(res.status(), 400);
+    let body = body_json(&mut res).await;
+    assert_eq!(body["error"], "syntax");
+    assert_eq!(body["line"], 2);
+    assert_eq!(body["column"], 11);
+}
+
+#[tokio::test]
+async fn json_data_errors() {
+    let mut res = post("/json", None, br#"{"name": "Nori"}"#).await;
+    assert_eq!(res.status(), 422);
+    let body = body_json(&mut res).await;
+    assert_eq!(body["error"], "invalid_data");
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("missing field `lives`"));
+    assert_eq!(body["line"], 1);
+
+    let mut res = post("/json", None, br#"{"name": "Nori", "lives": 900}"#).await;
+    assert_eq!(res.status(), 422);
+    let body = body_json(&mut res).await;
+    assert_eq!(body["error"], "invalid_data");
+    assert_eq!(body["line"], 1);
+
+    let res = post(
+        "/json",
+        Some("application/vnd.api+json"),
+        br#"{"name": "Nori", "lives": 9}"#,
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn form_errors() {
+    let res = post("/form", None, b"name=Nori&lives=9").await;
+    assert_eq!(res.status(), 200);
+
+    let mut res = post("/form", None, b"name=Nori").await;
+    assert_eq!(res.status(), 422);
+    let body = body_json(&mut res).await;
+    assert_eq!(body["error"], "invalid_data");
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("missing field `lives`"));
+
+    let mut res = post("/form", None, b"name=Nori&lives=many").await;
+    assert_eq!(res.status(), 422);
+    assert_eq!(body_json(&mut res).await["error"], "invalid_data");
+}
+
+#[tokio::test]
+async fn wrong_content_types() {
+    let mut res = post("/json", Some("text/plain"), b"{}").await;
+    assert_eq!(res.status(), 415);
+    assert_eq!(
+        body_json(&mut res).await,
+        json!({
+            "error": "unsupported_media_type",
+            "message": "expected a application/json body, found text/plain",
+            "expected": "application/json",
+        })
+    );
+
+    let res = post("/form", Some("application/json"), b"{}").await;
+    assert_eq!(res.status(), 415);
+
+    let mut res = post("/multipart", None, b"").await;
+    assert_eq!(res.status(), 415);
+    assert_eq!(body_json(&mut res).await["expected"], "multipart/form-data");
+
+    let mut res = post("/multipart", Some("multipart/form-data"), b"").await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body_json(&mut res).await["error"], "syntax");
+}
+
+#[tokio::test]
+async fn too_large() {
+    let mut res = post("/string", None, &[b'a'; 100]).await;
+    assert_eq!(res.status(), 413);
+    assert_eq!(
+        body_json(&mut res).await,
+        json!({
+            "error": "too_large",
+            "message": "request body is larger than 64 bytes",
+            "limit": 64,
+        })
+    );
+}
@@ -0,0 +1,163 @@
+use bytes::Bytes;
+use hyper::Body;
+use serde::Deserialize;
+use std::sync::Arc;
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::body_string;
+
+#[derive(Deserialize)]
+struct Message {
+    text: String,
+}
+
+async fn echo(mut req: Request<()>) -> Result<String, tide::Error> {
+    let body = req.body_string().await?;
+    Ok(body)
+}
+
+fn app() -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.body_limit(16);
+    app.at("/echo").post(echo);
+    app.at("/large").body_limit(64).post(echo);
+    app.at("/large/nested").post(echo);
+    app.at("/per-call").post(|mut req: Request<()>| async move {
+        let body = req.set_body_limit(4).body_bytes().await?;
+        Ok::<_, tide::Error>(format!("{}", body.len()))
+    });
+    app.at("/json").post(|mut req: Request<()>| async move {
+        let message: Message = req.body_json().await?;
+        Ok::<_, tide::Error>(message.text)
+    });
+    app.at("/form").post(|mut req: Request<()>| async move {
+        let message: Message = req.body_form().await?;
+        Ok::<_, tide::Error>(message.text)
+    });
+    app.at("/multipart")
+        .post(|mut req: Request<()>| async move {
+            let mut multipart = req.body_multipart()?;
+            while let Some(field) = multipart.next_field().await? {
+                field.bytes().await?;
+            }
+            Ok::<_, tide::Error>("")
+        });
+    app.scope("/scoped", |scope| {
+        scope.body_limit(2);
+        scope.at("/echo").post(echo);
+    });
+    app.into_http_service()
+}
+
+fn request(path: &str, length: Option<usize>, body: Body) -> Request<()> {
+    let content_type = match path {
+        "/json" => "application/json",
+        "/form" => "application/x-www-form-urlencoded",
+        "/multipart" => "multipart/form-data; boundary=X-BOUNDARY",
+        _ => "text/plain",
+    };
+    let mut req = hyper::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("Content-Type", content_type);
+    if let Some(length) = length {
+        req = req.header("Content-Length", length);
+    }
+    Request::new(Arc::new(()), req.body(body).unwrap(), vec![])
+}
+
+async fn post(path: &str, body: &'static str) -> Response {
+    app()
+        .call(request(path, Some(body.len()), Body::from(body)))
+        .await
+}
+
+/// Send a chunked body, without a `Content-Length`.
+async fn post_chunked(path: &str, body: &'static str) -> Response {
+    let chunks = body
+        .as_bytes()
+        .chunks(3)
+        .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk)));
+    let body = Body::wrap_stream(futures::stream::iter(chunks));
+    app().call(request(path, None, body)).await
+}
+
+const SMALL: &str = "0123456789";
+const LARGE: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+#[tokio::test]
+async fn server_limit() {
+    let mut res = post("/echo", SMALL).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, SMALL);
+
+    let res = post("/echo", LARGE).await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn chunked_bodies_are_aborted() {
+    let res = post_chunked("/echo", SMALL).await;
+    assert_eq!(res.status(), 200);
+
+    let res = post_chunked("/echo", LARGE).await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn content_length_is_checked_before_reading() {
+    // The body never ends, so only checking its length can answer.
+    let body = Body::wrap_stream(futures::stream::pending::<Result<Bytes, std::io::Error>>());
+    let res = app().call(request("/echo", Some(1000), body)).await;
+    assert_eq!(res.status(), 413);
+
+    let body = Body::wrap_stream(futures::stream::pending::<Result<Bytes, std::io::Error>>());
+    let res = app().call(request("/multipart", Some(1000), body)).await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn route_and_scope_limits() {
+    let res = post("/large", LARGE).await;
+    assert_eq!(res.status(), 200);
+    let res = post("/large/nested", LARGE).await;
+    assert_eq!(res.status(), 413);
+
+    let res = post("/scoped/echo", "abc").await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn per_call_limit() {
+    let mut res = post("/per-call", "abcd").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, "4");
+
+    let res = post_chunked("/per-call", "abcde").await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn body_readers_honor_the_limit() {
+    let res = post("/json", r#"{"text":"hi"}"#).await;
+    assert_eq!(res.status(), 200);
+    let res = post("/json", r#"{"text":"hello, world!"}"#).await;
+    assert_eq!(res.status(), 413);
+
+    let res = post("/form", "text=hi").await;
+    assert_eq!(res.status(), 200);
+    let res = post_chunked("/form", "text=hello%2C+world%21").await;
+    assert_eq!(res.status(), 413);
+
+    let multipart = "--X-BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"text\"\r\n\
+        \r\n\
+        hello, world!\r\n\
+        --X-BOUNDARY--\r\n";
+    let res = post("/multipart", multipart).await;
+    assert_eq!(res.status(), 413);
+    let res = post_chunked("/multipart", multipart).await;
+    assert_eq!(res.status(), 413);
+}
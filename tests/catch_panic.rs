@@ -0,0 +1,85 @@
+use hyper::Body;
+use std::sync::Arc;
+use tide::middleware::CatchPanic;
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::body_string;
+
+async fn get(app: &tide::server::Service<()>, path: &str) -> Response {
+    let req = hyper::Request::get(path).body(Body::empty()).unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+fn app(middleware: CatchPanic) -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.middleware(middleware);
+    app.at("/ok").get(|_| async { "ok" });
+    app.at("/str").get(|_| async {
+        if true {
+            panic!("boom");
+        }
+        ""
+    });
+    app.at("/string").get(|_| async {
+        let id = 7;
+        if true {
+            panic!("no user {}", id);
+        }
+        ""
+    });
+    app.at("/any").get(|_| async {
+        if true {
+            std::panic::panic_any(42);
+        }
+        ""
+    });
+    app.into_http_service()
+}
+
+#[tokio::test]
+async fn catches_panics() {
+    let app = app(CatchPanic::new());
+
+    let mut res = get(&app, "/ok").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, "ok");
+
+    let mut res = get(&app, "/str").await;
+    assert_eq!(res.status(), 500);
+    let cause = res.cause().unwrap().to_string();
+    assert!(
+        cause.starts_with("panicked at tests/catch_panic.rs:"),
+        "{}",
+        cause
+    );
+    assert!(cause.ends_with(": boom"), "{}", cause);
+    assert_eq!(body_string(&mut res).await, "");
+
+    let res = get(&app, "/string").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.cause().unwrap().to_string().ends_with(": no user 7"));
+
+    let res = get(&app, "/any").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.cause().unwrap().to_string().ends_with(": Box<dyn Any>"));
+
+    // The server keeps serving after a panic.
+    assert_eq!(get(&app, "/ok").await.status(), 200);
+}
+
+#[tokio::test]
+async fn describes_panics_on_demand() {
+    let app = app(CatchPanic::new().details(true));
+    let mut res = get(&app, "/string").await;
+    assert_eq!(res.status(), 500);
+    assert_eq!(res.headers()["Content-Type"], "text/plain; charset=utf-8");
+    let body = body_string(&mut res).await;
+    assert!(
+        body.starts_with("panicked at tests/catch_panic.rs:"),
+        "{}",
+        body
+    );
+    assert!(body.ends_with(": no user 7"), "{}", body);
+}
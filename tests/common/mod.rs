@@ -0,0 +1,17 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use bytes::Buf;
+use hyper::body;
+use tide::Response;
+
+/// Read the whole body of `res`.
+pub async fn body_bytes(res: &mut Response) -> Vec<u8> {
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    body.to_vec()
+}
+
+/// Read the whole body of `res` as UTF-8.
+pub async fn body_string(res: &mut Response) -> String {
+    String::from_utf8(body_bytes(res).await).unwrap()
+}
@@ -57,7 +57,7 @@ async fn successfully_retrieve_request_cookie() {
 
     let body = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
 
-    assert_eq!(&body[..], &*b"RequestCookieValue");
+    assert_eq!(&body[..], b"RequestCookieValue");
 }
 
 #[tokio::test]
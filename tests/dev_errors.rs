@@ -0,0 +1,84 @@
+use hyper::Body;
+use std::fmt;
+use std::sync::Arc;
+use tide::middleware::DevErrors;
+use tide::{Endpoint, Error, Request, Response, StatusCode};
+
+mod common;
+
+use common::body_string;
+
+#[derive(Debug)]
+struct LookupError(std::io::Error);
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("user lookup failed")
+    }
+}
+
+impl std::error::Error for LookupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+async fn get(app: &tide::server::Service<()>, path: &str) -> Response {
+    let req = hyper::Request::get(path)
+        .header("Cookie", "session=<abc>")
+        .header("X-Trace", "t-1")
+        .body(Body::empty())
+        .unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+fn app(middleware: DevErrors) -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.middleware(middleware);
+    app.at("/users/:id").get(|req: Request<()>| async move {
+        assert_eq!(req.route(), Some("/users/:id"));
+        let err = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        Err::<String, _>(Error::new(StatusCode::NOT_FOUND, LookupError(err)))
+    });
+    app.at("/teapot")
+        .get(|_| async { Response::new(418).body_string("short and stout".to_string()) });
+    app.into_http_service()
+}
+
+#[tokio::test]
+async fn disabled_by_default() {
+    let app = app(DevErrors::new());
+    let mut res = get(&app, "/users/7").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(body_string(&mut res).await, "user lookup failed");
+}
+
+#[tokio::test]
+async fn renders_error_pages() {
+    let app = app(DevErrors::new().enabled(true));
+    let mut res = get(&app, "/users/7?full=1").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.headers()["Content-Type"], "text/html; charset=utf-8");
+    assert!(res.cause().unwrap().is::<LookupError>());
+    let page = body_string(&mut res).await;
+    assert!(page.contains("<h1>404 Not Found</h1>"));
+    assert!(page.contains("<li>user lookup failed</li>\n<li>no such file</li>"));
+    assert!(page.contains("<h2>Backtrace</h2>"));
+    assert!(page.contains("<code>GET /users/7?full=1 HTTP/1.1</code>"));
+    assert!(page.contains("Route: <code>/users/:id</code>"));
+    assert!(page.contains("<tr><th>id</th><td><code>7</code></td></tr>"));
+    assert!(page.contains("<tr><th>x-trace</th><td><code>t-1</code></td></tr>"));
+    assert!(page.contains("<tr><th>session</th><td><code>&lt;abc&gt;</code></td></tr>"));
+
+    // The router's errors have no cause and no route.
+    let mut res = get(&app, "/nowhere").await;
+    assert_eq!(res.status(), 404);
+    let page = body_string(&mut res).await;
+    assert!(page.contains("The response has no cause."));
+    assert!(page.contains("Route: <code>none</code>"));
+
+    // Responses describing the error in their own way are left alone.
+    let mut res = get(&app, "/teapot").await;
+    assert_eq!(res.status(), 418);
+    assert_eq!(body_string(&mut res).await, "short and stout");
+}
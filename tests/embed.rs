@@ -0,0 +1,91 @@
+use bytes::Buf;
+use hyper::{body, Body};
+use std::sync::Arc;
+use tide::fs::{include_dir, Dir, ServeDir};
+use tide::{Endpoint, Request, Response};
+
+static ASSETS: Dir = include_dir!("tests/fixtures/static");
+
+async fn get_with(path: &str, headers: &[(&str, &str)]) -> Response {
+    let mut app = tide::new();
+    app.at("/assets").serve_dir(ASSETS);
+    app.at("/compressed")
+        .serve_dir(ServeDir::embedded(ASSETS).precompressed(true));
+    let app = app.into_http_service();
+
+    let mut req = hyper::Request::get(path);
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let req = Request::new(Arc::new(()), req.body(Body::empty()).unwrap(), vec![]);
+    app.call(req).await
+}
+
+async fn body_bytes(res: &mut Response) -> Vec<u8> {
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    body.to_vec()
+}
+
+#[tokio::test]
+async fn serves_embedded_files() {
+    let mut res = get_with("/assets/css/style.css", &[]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/css");
+    assert_eq!(res.headers()["Content-Length"], "21");
+    assert!(res.headers().get("Last-Modified").is_none());
+    assert_eq!(body_bytes(&mut res).await, b"body { color: red; }\n");
+
+    let mut res = get_with("/assets/", &[]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/html");
+    let index = body_bytes(&mut res).await;
+    assert_eq!(index, ASSETS.get_file("index.html").unwrap().contents());
+
+    let res = get_with("/assets/docs", &[]).await;
+    assert_eq!(res.status(), 301);
+    assert_eq!(res.headers()["Location"], "/assets/docs/");
+    assert_eq!(get_with("/assets/docs/", &[]).await.status(), 404);
+    assert_eq!(get_with("/assets/missing.txt", &[]).await.status(), 404);
+    assert_eq!(
+        get_with("/assets/%2e%2e/Cargo.toml", &[]).await.status(),
+        403
+    );
+}
+
+#[tokio::test]
+async fn content_hash_etags() {
+    let res = get_with("/assets/docs/digits.txt", &[]).await;
+    let etag = res.headers()["ETag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with("\"14-"));
+    assert_eq!(
+        get_with("/assets/docs/digits.txt", &[]).await.headers()["ETag"],
+        etag.as_str()
+    );
+    assert_ne!(
+        get_with("/assets/docs/readme.txt", &[]).await.headers()["ETag"],
+        etag.as_str()
+    );
+
+    let res = get_with("/assets/docs/digits.txt", &[("If-None-Match", &etag)]).await;
+    assert_eq!(res.status(), 304);
+
+    let headers = [("Range", "bytes=10-12"), ("If-Range", etag.as_str())];
+    let mut res = get_with("/assets/docs/digits.txt", &headers).await;
+    assert_eq!(res.status(), 206);
+    assert_eq!(res.headers()["Content-Range"], "bytes 10-12/20");
+    assert_eq!(body_bytes(&mut res).await, b"abc");
+}
+
+#[tokio::test]
+async fn embedded_precompressed_files() {
+    let mut res = get_with("/compressed/js/app.js", &[("Accept-Encoding", "gzip")]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/javascript");
+    assert_eq!(res.headers()["Content-Encoding"], "gzip");
+    assert_eq!(res.headers()["Vary"], "Accept-Encoding");
+    let gz = ASSETS.get_file("js/app.js.gz").unwrap().contents();
+    assert_eq!(body_bytes(&mut res).await, gz);
+
+    let res = get_with("/assets/js/app.js", &[("Accept-Encoding", "gzip")]).await;
+    assert!(res.headers().get("Content-Encoding").is_none());
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+use std::io;
+use tide::error::{BodyError, ResultExt};
+use tide::{Error, IntoResponse, Response, StatusCode};
+
+mod common;
+
+use common::body_string;
+
+#[derive(Debug)]
+struct Outer(io::Error);
+
+impl fmt::Display for Outer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("could not load the user")
+    }
+}
+
+impl std::error::Error for Outer {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+fn load() -> tide::Result<()> {
+    Err(Outer(io::Error::other("disk on fire")))?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn errors_default_to_server_errors() {
+    let err = load().unwrap_err();
+    assert_eq!(err.status(), 500);
+    assert!(err.is::<Outer>());
+    let chain: Vec<_> = err.chain().map(|e| e.to_string()).collect();
+    assert_eq!(chain, vec!["could not load the user", "disk on fire"]);
+
+    let mut res = err.into_response();
+    assert_eq!(res.status(), 500);
+    assert_eq!(body_string(&mut res).await, "");
+    let cause = res.cause().unwrap().downcast_ref::<Outer>().unwrap();
+    assert_eq!(cause.0.to_string(), "disk on fire");
+}
+
+#[tokio::test]
+async fn errors_carry_their_status() {
+    let err = Error::new(StatusCode::NOT_FOUND, "no such user");
+    assert_eq!(err.status(), 404);
+    assert_eq!(err.to_string(), "no such user");
+    let mut res = err.into_response();
+    assert_eq!(res.status(), 404);
+    assert_eq!(body_string(&mut res).await, "no such user");
+
+    let mut err = Error::new(StatusCode::CONFLICT, Outer(io::Error::other("taken")));
+    err.set_status(StatusCode::GONE);
+    assert_eq!(err.into_response().status(), 410);
+
+    let err = Error::from_status(StatusCode::FORBIDDEN);
+    assert_eq!(err.to_string(), "Forbidden");
+}
+
+#[test]
+fn only_server_errors_capture_backtraces() {
+    std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    let server_error = Error::new(StatusCode::INTERNAL_SERVER_ERROR, "disk on fire");
+    // Whether backtraces are enabled is decided once per process, possibly by another test.
+    if server_error.backtrace().is_some() {
+        assert!(Error::new(StatusCode::NOT_FOUND, "no such user")
+            .backtrace()
+            .is_none());
+        assert!(Error::from(Response::new(405)).backtrace().is_none());
+        assert!(Error::from(io::Error::other("disk on fire"))
+            .backtrace()
+            .is_some());
+    }
+}
+
+#[test]
+fn client_io_errors_become_bad_requests() {
+    for kind in &[
+        io::ErrorKind::InvalidData,
+        io::ErrorKind::InvalidInput,
+        io::ErrorKind::UnexpectedEof,
+    ] {
+        let err = Error::from(io::Error::from(*kind));
+        assert_eq!(err.status(), 400);
+        assert!(err.into_response().cause().is_some());
+    }
+}
+
+#[test]
+fn downcasting() {
+    let err = Error::from(BodyError::TooLarge { limit: 1 });
+    assert_eq!(err.status(), 413);
+    assert!(err.downcast_ref::<io::Error>().is_none());
+    let err = err.downcast::<io::Error>().unwrap_err();
+    assert_eq!(
+        err.downcast::<BodyError>().unwrap(),
+        BodyError::TooLarge { limit: 1 }
+    );
+}
+
+#[test]
+fn responses_turn_back_into_themselves() {
+    let res = Response::new(418).set_header("X-Teapot", "short and stout");
+    let err = Error::from(res);
+    assert_eq!(err.status(), 418);
+    assert_eq!(err.to_string(), "I'm a teapot");
+    let res = err.into_response();
+    assert_eq!(res.status(), 418);
+    assert_eq!(res.headers()["X-Teapot"], "short and stout");
+}
+
+#[test]
+fn result_ext_keeps_the_cause() {
+    let err = "x".parse::<u8>().client_err().unwrap_err();
+    assert_eq!(err.status(), 400);
+    assert!(err.is::<std::num::ParseIntError>());
+    let res = err.into_response();
+    assert!(res
+        .cause()
+        .unwrap()
+        .downcast_ref::<std::num::ParseIntError>()
+        .is_some());
+    assert!(Response::new(200).cause().is_none());
+}
@@ -0,0 +1,135 @@
+use hyper::Body;
+use std::sync::{Arc, Mutex};
+use tide::{Endpoint, Error, IntoResponse, Request, Response, StatusCode};
+
+mod common;
+
+use common::body_string;
+
+async fn get(app: &tide::server::Service<()>, method: &str, path: &str) -> Response {
+    let req = hyper::Request::builder()
+        .method(method)
+        .uri(path)
+        .header("X-Request-Id", "42")
+        .body(Body::empty())
+        .unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+fn app() -> tide::Server<()> {
+    let mut app = tide::new();
+    app.at("/ok").get(|_| async { "ok" });
+    app.at("/missing")
+        .get(|_| async { Err::<String, _>(Error::new(StatusCode::NOT_FOUND, "no such user")) });
+    app.at("/broken").get(|_| async {
+        std::fs::read_to_string("/does/not/exist")?;
+        Ok::<_, Error>("")
+    });
+    app.at("/teapot")
+        .get(|_| async { Response::new(418).body_string("short and stout".to_string()) });
+    app.at("/readonly")
+        .post(|_| async { Response::new(405).set_header("Allow", "GET, HEAD") });
+    app
+}
+
+#[tokio::test]
+async fn on_error_sees_every_error() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut app = app();
+    let log = seen.clone();
+    app.on_error(move |err, req| {
+        log.lock().unwrap().push(format!(
+            "{} {} {} {}",
+            req.method(),
+            req.uri(),
+            req.headers()["X-Request-Id"].to_str().unwrap(),
+            err.status().as_u16(),
+        ));
+        let is_io = err.is::<std::io::Error>();
+        let mut res = err.into_response();
+        if is_io {
+            res = res.body_string("rendered".to_string());
+        }
+        res
+    });
+    let app = app.into_http_service();
+
+    let mut res = get(&app, "GET", "/ok").await;
+    assert_eq!(body_string(&mut res).await, "ok");
+
+    let mut res = get(&app, "GET", "/missing").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(body_string(&mut res).await, "no such user");
+
+    let mut res = get(&app, "GET", "/broken").await;
+    assert_eq!(res.status(), 500);
+    assert_eq!(body_string(&mut res).await, "rendered");
+
+    let mut res = get(&app, "GET", "/teapot").await;
+    assert_eq!(res.status(), 418);
+    assert_eq!(body_string(&mut res).await, "short and stout");
+
+    // Errors of the router's fallbacks go through the handler as well.
+    assert_eq!(get(&app, "GET", "/nowhere").await.status(), 404);
+    assert_eq!(get(&app, "POST", "/ok").await.status(), 405);
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            "GET /missing 42 404",
+            "GET /broken 42 500",
+            "GET /teapot 42 418",
+            "GET /nowhere 42 404",
+            "POST /ok 42 405",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn error_pages() {
+    let mut app = app();
+    app.error_page(404, |req: Request<()>| async move {
+        format!("<h1>{} not found</h1>", req.uri().path())
+    });
+    app.on_error(|err, _| Response::new(err.status().as_u16()).body_string("fallback".to_string()));
+    let app = app.into_http_service();
+
+    for path in &["/missing", "/nowhere"] {
+        let mut res = get(&app, "GET", path).await;
+        assert_eq!(res.status(), 404);
+        assert_eq!(
+            body_string(&mut res).await,
+            format!("<h1>{} not found</h1>", path)
+        );
+    }
+
+    let mut res = get(&app, "GET", "/missing").await;
+    let cause = res.cause().unwrap();
+    assert_eq!(cause.to_string(), "no such user");
+    body_string(&mut res).await;
+
+    let mut res = get(&app, "GET", "/broken").await;
+    assert_eq!(res.status(), 500);
+    assert_eq!(body_string(&mut res).await, "fallback");
+}
+
+#[tokio::test]
+async fn error_pages_keep_the_headers_of_the_error() {
+    let mut app = app();
+    app.error_page(405, |_| async { "<h1>Method not allowed</h1>" });
+    let app = app.into_http_service();
+
+    let mut res = get(&app, "POST", "/readonly").await;
+    assert_eq!(res.status(), 405);
+    assert_eq!(res.headers()["Allow"], "GET, HEAD");
+    assert_eq!(res.headers()["Content-Type"], "text/plain; charset=utf-8");
+    assert_eq!(body_string(&mut res).await, "<h1>Method not allowed</h1>");
+}
+
+#[tokio::test]
+async fn errors_are_untouched_without_handlers() {
+    let app = app().into_http_service();
+    let res = get(&app, "GET", "/broken").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.cause().unwrap().is::<std::io::Error>());
+}
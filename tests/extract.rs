@@ -0,0 +1,174 @@
+use bytes::Buf;
+use futures::future::BoxFuture;
+use hyper::{body, Body};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tide::extract::{handler, Form, FromRequest, Json, Path, Query, State};
+use tide::{Endpoint, Error, Request, Response, StatusCode};
+
+#[derive(Default)]
+struct Counter(AtomicUsize);
+
+#[derive(Deserialize)]
+struct Filter {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    text: String,
+}
+
+async fn add_comment(
+    Path(id): Path<u64>,
+    Query(filter): Query<Filter>,
+    Json(comment): Json<Comment>,
+    State(counter): State<Counter>,
+) -> String {
+    let count = counter.0.fetch_add(1, Ordering::SeqCst) + 1;
+    format!(
+        "{} on {} (limit {:?}, comment {})",
+        comment.text, id, filter.limit, count
+    )
+}
+
+async fn show_post(Path((user, post)): Path<(String, u64)>) -> String {
+    format!("post {} of {}", post, user)
+}
+
+struct ApiKey(String);
+
+impl<State: Send + Sync + 'static> FromRequest<State> for ApiKey {
+    fn from_request<'a>(req: &'a mut Request<State>) -> BoxFuture<'a, Result<Self, Error>> {
+        let key = req
+            .headers()
+            .get("X-Api-Key")
+            .and_then(|value| value.to_str().ok())
+            .map(|key| ApiKey(key.to_string()))
+            .ok_or_else(|| Error::new(StatusCode::UNAUTHORIZED, "missing API key"));
+        Box::pin(async move { key })
+    }
+}
+
+fn app() -> tide::server::Service<Counter> {
+    let mut app = tide::with_state(Counter::default());
+    app.at("/posts/:id/comments").post(handler(add_comment));
+    app.at("/users/:user/posts/:post").get(handler(show_post));
+    app.at("/health").get(handler(|| async { "ok" }));
+    app.at("/whoami")
+        .get(handler(|key: Option<ApiKey>| async move {
+            key.map(|ApiKey(key)| key).unwrap_or_else(|| "anonymous".to_string())
+        }))
+        .post(handler(|key: ApiKey, Form(form): Form<Filter>| async move {
+            format!("{} {:?}", key.0, form.limit)
+        }));
+    app.into_http_service()
+}
+
+async fn call(
+    app: &tide::server::Service<Counter>,
+    req: hyper::http::request::Builder,
+    body: &'static str,
+) -> (StatusCode, String) {
+    let req = req.body(Body::from(body)).unwrap();
+    let mut res: Response = app
+        .call(Request::new(Arc::new(Counter::default()), req, vec![]))
+        .await;
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    (res.status(), String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn post(uri: &str) -> hyper::http::request::Builder {
+    hyper::Request::post(uri).header("Content-Type", "application/json")
+}
+
+#[tokio::test]
+async fn extracts_arguments() {
+    let app = app();
+    let comment = r#"{"text": "nice"}"#;
+    let res = call(&app, post("/posts/7/comments?limit=3"), comment).await;
+    assert_eq!(
+        res,
+        (
+            StatusCode::OK,
+            "nice on 7 (limit Some(3), comment 1)".to_string()
+        )
+    );
+    let res = call(&app, post("/posts/8/comments"), comment).await;
+    assert_eq!(
+        res,
+        (
+            StatusCode::OK,
+            "nice on 8 (limit None, comment 2)".to_string()
+        )
+    );
+
+    let res = call(&app, hyper::Request::get("/users/ann/posts/3"), "").await;
+    assert_eq!(res, (StatusCode::OK, "post 3 of ann".to_string()));
+
+    let res = call(&app, hyper::Request::get("/health"), "").await;
+    assert_eq!(res, (StatusCode::OK, "ok".to_string()));
+}
+
+#[tokio::test]
+async fn answers_failed_extractions() {
+    let app = app();
+    let comment = r#"{"text": "nice"}"#;
+
+    let (status, body) = call(&app, post("/posts/seven/comments"), comment).await;
+    assert_eq!(status, 400);
+    assert_eq!(
+        body,
+        "invalid route parameters: cannot parse `seven` as u64"
+    );
+
+    let (status, _) = call(&app, post("/posts/7/comments?limit=all"), comment).await;
+    assert_eq!(status, 400);
+
+    let (status, _) = call(&app, post("/posts/7/comments"), r#"{"txt": "nice"}"#).await;
+    assert_eq!(status, 422);
+
+    let req = hyper::Request::post("/posts/7/comments").header("Content-Type", "text/plain");
+    let (status, _) = call(&app, req, comment).await;
+    assert_eq!(status, 415);
+}
+
+#[tokio::test]
+async fn supports_custom_and_optional_extractors() {
+    let app = app();
+    let res = call(&app, hyper::Request::get("/whoami"), "").await;
+    assert_eq!(res, (StatusCode::OK, "anonymous".to_string()));
+
+    let req = hyper::Request::get("/whoami").header("X-Api-Key", "k-1");
+    let res = call(&app, req, "").await;
+    assert_eq!(res, (StatusCode::OK, "k-1".to_string()));
+
+    let form = |req: hyper::http::request::Builder| {
+        req.header("Content-Type", "application/x-www-form-urlencoded")
+    };
+    let (status, body) = call(&app, form(hyper::Request::post("/whoami")), "limit=2").await;
+    assert_eq!(status, 401);
+    assert_eq!(body, "missing API key");
+
+    let req = form(hyper::Request::post("/whoami")).header("X-Api-Key", "k-1");
+    let res = call(&app, req, "limit=2").await;
+    assert_eq!(res, (StatusCode::OK, "k-1 Some(2)".to_string()));
+}
+
+#[tokio::test]
+async fn extracts_raw_bodies() {
+    use tide::Bytes;
+
+    let mut app = tide::new();
+    app.at("/echo")
+        .post(handler(|Bytes(body): Bytes| async move { Bytes(body) }));
+    let app = app.into_http_service();
+    let req = hyper::Request::post("/echo")
+        .body(Body::from("ping"))
+        .unwrap();
+    let mut res = app.call(Request::new(Arc::new(()), req, vec![])).await;
+    assert_eq!(res.headers()["Content-Type"], "application/octet-stream");
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    assert_eq!(body, "ping");
+}
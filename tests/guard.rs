@@ -0,0 +1,131 @@
+use bytes::Buf;
+use hyper::{body, Body};
+use std::sync::Arc;
+use tide::guard::{accepts, content_type, header, header_with};
+use tide::{Endpoint, Request, Response};
+
+fn app() -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.at("/negotiate")
+        .guard(accepts("application/json"))
+        .get(|_| async { "json" });
+    app.at("/negotiate")
+        .guard(accepts("text/html"))
+        .get(|_| async { "html" });
+    app.at("/upload")
+        .guard(content_type("application/json"))
+        .post(|_| async { "json" });
+    app.at("/upload")
+        .guard(content_type("text/*"))
+        .post(|_| async { "text" });
+    app.at("/anything")
+        .guard(content_type("*/*"))
+        .post(|_| async { "any" });
+    app.at("/versioned")
+        .guard(header("X-Api-Version", "2"))
+        .get(|_| async { "v2" });
+    app.at("/versioned")
+        .guard(header_with("X-Api-Version", |v| v.starts_with('3')))
+        .get(|_| async { "v3" });
+    app.at("/versioned").get(|_| async { "v1" });
+    app.into_http_service()
+}
+
+async fn call(req: hyper::Request<Body>) -> (u16, String) {
+    let req = Request::new(Arc::new(()), req, vec![]);
+    let mut res: Response = app().call(req).await;
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    (res.status().as_u16(), String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn accept_guards() {
+    let req = hyper::Request::get("/negotiate")
+        .header("Accept", "text/html,application/xhtml+xml;q=0.9")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "html".to_string()));
+
+    let req = hyper::Request::get("/negotiate")
+        .header("Accept", "application/*")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "json".to_string()));
+
+    let req = hyper::Request::get("/negotiate")
+        .header("Accept", "application/json;q=0, */*")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "html".to_string()));
+
+    let req = hyper::Request::get("/negotiate")
+        .header("Accept", "image/png")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await.0, 406);
+}
+
+#[tokio::test]
+async fn content_type_guards() {
+    let req = hyper::Request::post("/upload")
+        .header("Content-Type", "application/json; charset=utf-8")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "json".to_string()));
+
+    let req = hyper::Request::post("/upload")
+        .header("Content-Type", "text/csv")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "text".to_string()));
+
+    let req = hyper::Request::post("/upload")
+        .header("Content-Type", "image/png")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await.0, 415);
+
+    let req = hyper::Request::post("/upload").body(Body::empty()).unwrap();
+    assert_eq!(call(req).await.0, 415);
+
+    let req = hyper::Request::post("/anything")
+        .header("Content-Type", "image/png")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "any".to_string()));
+
+    let req = hyper::Request::post("/anything").body(Body::empty()).unwrap();
+    assert_eq!(call(req).await.0, 415);
+}
+
+#[tokio::test]
+async fn header_guards_fall_back_to_unguarded_endpoint() {
+    let req = hyper::Request::get("/versioned")
+        .header("X-Api-Version", "2")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "v2".to_string()));
+
+    let req = hyper::Request::get("/versioned")
+        .header("X-Api-Version", "3.1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await, (200, "v3".to_string()));
+
+    let req = hyper::Request::get("/versioned").body(Body::empty()).unwrap();
+    assert_eq!(call(req).await, (200, "v1".to_string()));
+}
+
+#[tokio::test]
+async fn unguarded_endpoints_still_replace_each_other() {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { "first" });
+    app.at("/").get(|_| async { "second" });
+    let app = app.into_http_service();
+
+    let req = hyper::Request::get("/").body(Body::empty()).unwrap();
+    let req = Request::new(Arc::new(()), req, vec![]);
+    let mut res: Response = app.call(req).await;
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"second");
+}
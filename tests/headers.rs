@@ -0,0 +1,98 @@
+use hyper::header::{HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING};
+use hyper::Body;
+use std::sync::Arc;
+use std::time::Duration;
+use tide::{CacheControl, ContentDisposition, Endpoint, Request, Response};
+
+#[test]
+fn fallible_setters_reject_invalid_headers() {
+    let res = Response::new(200)
+        .try_set_header("X-Request-Id", "a1b2")
+        .unwrap()
+        .try_append_header(
+            HeaderName::from_static("x-tag"),
+            HeaderValue::from_static("a"),
+        )
+        .unwrap()
+        .try_append_header("X-Tag", "b".to_string())
+        .unwrap();
+    assert_eq!(res.headers()["X-Request-Id"], "a1b2");
+    let tags: Vec<_> = res.headers().get_all("X-Tag").iter().collect();
+    assert_eq!(tags, vec!["a", "b"]);
+
+    let err = Response::new(200)
+        .try_set_header("X-Name", "a\r\nSet-Cookie: b")
+        .unwrap_err();
+    assert_eq!(err.status(), 500);
+    assert!(Response::new(200).try_set_header("X Name", "a").is_err());
+}
+
+#[test]
+fn sets_typed_headers() {
+    let res = Response::new(200)
+        .set_cache_control(
+            CacheControl::new()
+                .public()
+                .max_age(Duration::from_secs(60))
+                .immutable(),
+        )
+        .set_content_disposition(ContentDisposition::inline())
+        .set_location("/search?q=caf\u{e9} au lait")
+        .append_vary(ACCEPT_ENCODING)
+        .append_vary(HeaderName::from_static("accept-encoding"))
+        .append_vary(ACCEPT);
+    assert_eq!(
+        res.headers()["Cache-Control"],
+        "public, max-age=60, immutable"
+    );
+    assert_eq!(res.headers()["Content-Disposition"], "inline");
+    assert_eq!(res.headers()["Location"], "/search?q=caf%C3%A9%20au%20lait");
+    assert_eq!(res.headers()["Vary"], "Accept-Encoding, Accept");
+
+    let res = Response::new(200)
+        .set_header("Vary", "*")
+        .append_vary(ACCEPT);
+    assert_eq!(res.headers()["Vary"], "*");
+}
+
+#[test]
+fn escapes_file_names() {
+    let disposition = ContentDisposition::attachment().filename("a \"b\"\\c.txt");
+    assert_eq!(
+        disposition.to_string(),
+        r#"attachment; filename="a \"b\"\\c.txt""#
+    );
+    let disposition = ContentDisposition::attachment().filename("r\u{e9}sum\u{e9}\n.pdf");
+    assert_eq!(
+        disposition.to_string(),
+        "attachment; filename=\"r_sum__.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9%0A.pdf"
+    );
+    let res = Response::new(200).set_content_disposition(disposition);
+    assert!(res.headers()["Content-Disposition"]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment"));
+}
+
+#[tokio::test]
+async fn reading_non_ascii_headers_does_not_panic() {
+    let mut app = tide::new();
+    app.at("/")
+        .get(|req: Request<()>| async move { format!("{:?}", req.header("X-Name")) });
+    app.at("/old").get(tide::redirect("/new page"));
+    let app = app.into_http_service();
+
+    let req = hyper::Request::get("/")
+        .header("X-Name", HeaderValue::from_bytes(b"caf\xc3\xa9").unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let mut res = app.call(Request::new(Arc::new(()), req, vec![])).await;
+    assert_eq!(res.status(), 200);
+    let body = hyper::body::to_bytes(res.take_body()).await.unwrap();
+    assert_eq!(body, "None");
+
+    let req = hyper::Request::get("/old").body(Body::empty()).unwrap();
+    let res = app.call(Request::new(Arc::new(()), req, vec![])).await;
+    assert_eq!(res.status(), 307);
+    assert_eq!(res.headers()["Location"], "/new%20page");
+}
@@ -0,0 +1,216 @@
+use bytes::Bytes;
+use futures::StreamExt;
+use hyper::Body;
+use serde::Deserialize;
+use std::sync::Arc;
+use tide::error::BodyError;
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::body_string;
+
+const BOUNDARY: &str = "X-BOUNDARY";
+
+const BODY: &str = "preamble\r\n\
+--X-BOUNDARY\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\
+\r\n\
+Holiday\r\n\
+--X-BOUNDARY\r\n\
+Content-Disposition: form-data; name=\"tags[]\"\r\n\
+\r\n\
+sea\r\n\
+--X-BOUNDARY\r\n\
+Content-Disposition: form-data; name=\"photo\"; filename=\"beach.txt\"\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+sand\r\n--X-BOUNDAR\r\nand sun\r\n\
+--X-BOUNDARY--\r\n\
+epilogue";
+
+fn request(content_type: &str, body: Body) -> Request<()> {
+    let req = hyper::Request::builder()
+        .method("POST")
+        .uri("/upload")
+        .header("Content-Type", content_type)
+        .body(body)
+        .unwrap();
+    Request::new(Arc::new(()), req, vec![])
+}
+
+fn form_request(body: Body) -> Request<()> {
+    request(&format!("multipart/form-data; boundary={}", BOUNDARY), body)
+}
+
+/// A body sent one byte at a time.
+fn trickle(body: &'static str) -> Body {
+    let chunks = body
+        .as_bytes()
+        .chunks(1)
+        .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk)));
+    Body::wrap_stream(futures::stream::iter(chunks))
+}
+
+async fn fields(mut req: Request<()>) -> Vec<(Option<String>, Option<String>, String)> {
+    let mut multipart = req.body_multipart().unwrap();
+    let mut fields = Vec::new();
+    while let Some(field) = multipart.next_field().await.unwrap() {
+        let name = field.name().map(String::from);
+        let file_name = field.file_name().map(String::from);
+        fields.push((name, file_name, field.text().await.unwrap()));
+    }
+    fields
+}
+
+#[tokio::test]
+async fn streams_fields() {
+    let expected = vec![
+        (Some("title".to_string()), None, "Holiday".to_string()),
+        (Some("tags[]".to_string()), None, "sea".to_string()),
+        (
+            Some("photo".to_string()),
+            Some("beach.txt".to_string()),
+            "sand\r\n--X-BOUNDAR\r\nand sun".to_string(),
+        ),
+    ];
+    assert_eq!(fields(form_request(Body::from(BODY))).await, expected);
+    assert_eq!(fields(form_request(trickle(BODY))).await, expected);
+}
+
+#[tokio::test]
+async fn exposes_field_headers() {
+    let mut req = form_request(Body::from(BODY));
+    let multipart = req.body_multipart().unwrap();
+    let fields: Vec<_> = multipart.collect().await;
+    assert_eq!(fields.len(), 3);
+    let photo = fields[2].as_ref().unwrap();
+    assert_eq!(photo.content_type(), Some(&mime::TEXT_PLAIN));
+    assert_eq!(photo.headers()["Content-Type"], "text/plain");
+    assert_eq!(fields[0].as_ref().unwrap().content_type(), None);
+}
+
+#[tokio::test]
+async fn skips_unread_fields() {
+    let mut req = form_request(trickle(BODY));
+    let mut multipart = req.body_multipart().unwrap();
+    let title = multipart.next_field().await.unwrap().unwrap();
+    assert_eq!(title.name(), Some("title"));
+    multipart.next_field().await.unwrap().unwrap();
+    let mut photo = multipart.next_field().await.unwrap().unwrap();
+    assert_eq!(photo.name(), Some("photo"));
+    assert!(photo.chunk().await.unwrap().is_some());
+    assert!(multipart.next_field().await.unwrap().is_none());
+
+    // Fields left behind are done.
+    assert!(photo.chunk().await.unwrap().is_none());
+    assert_eq!(title.bytes().await.unwrap(), b"");
+}
+
+#[derive(Debug, Deserialize)]
+struct Upload {
+    title: String,
+    tags: Vec<String>,
+}
+
+#[tokio::test]
+async fn collects_forms() {
+    let mut req = form_request(trickle(BODY));
+    let form = req
+        .body_multipart()
+        .unwrap()
+        .into_form::<Upload>()
+        .await
+        .unwrap();
+    assert_eq!(form.fields.title, "Holiday");
+    assert_eq!(form.fields.tags, vec!["sea"]);
+    assert_eq!(form.files.len(), 1);
+
+    let photo = form.file("photo").unwrap();
+    assert_eq!(photo.file_name(), Some("beach.txt"));
+    assert_eq!(photo.content_type(), Some(&mime::TEXT_PLAIN));
+    assert_eq!(photo.size(), 26);
+    let contents = std::fs::read_to_string(photo.path()).unwrap();
+    assert_eq!(contents, "sand\r\n--X-BOUNDAR\r\nand sun");
+
+    let path = photo.path().to_path_buf();
+    drop(form);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn enforces_limits() {
+    let mut req = form_request(Body::from(BODY));
+    let mut multipart = req.body_multipart().unwrap().part_limit(10);
+    let mut errors = Vec::new();
+    while let Some(field) = multipart.next_field().await.unwrap() {
+        if let Err(e) = field.bytes().await {
+            errors.push(e.downcast::<BodyError>().unwrap());
+        }
+    }
+    assert_eq!(errors, vec![BodyError::TooLarge { limit: 10 }]);
+
+    let mut req = form_request(trickle(BODY));
+    let mut multipart = req.body_multipart().unwrap().total_limit(100);
+    let err = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) => {
+                if let Err(e) = field.bytes().await {
+                    break e;
+                }
+            }
+            Ok(None) => panic!("the limit was not enforced"),
+            Err(e) => break e,
+        }
+    };
+    assert_eq!(err.status(), 413);
+    assert_eq!(
+        err.downcast_ref::<BodyError>(),
+        Some(&BodyError::TooLarge { limit: 100 })
+    );
+}
+
+#[tokio::test]
+async fn rejects_other_bodies() {
+    let mut app = tide::new();
+    app.at("/upload").post(|mut req: Request<()>| async move {
+        req.body_multipart()?;
+        Ok::<_, tide::Error>(Response::new(204))
+    });
+    let app = app.into_http_service();
+
+    let res = app
+        .call(request("application/json", Body::from("{}")))
+        .await;
+    assert_eq!(res.status(), 415);
+
+    let res = app
+        .call(request("multipart/form-data", Body::empty()))
+        .await;
+    assert_eq!(res.status(), 400);
+
+    let mut res = app
+        .call(request(
+            "multipart/form-data; boundary=x",
+            Body::from("--x\r\n\r\n"),
+        ))
+        .await;
+    assert_eq!(res.status(), 204);
+    assert_eq!(body_string(&mut res).await, "");
+}
+
+#[tokio::test]
+async fn reports_truncated_bodies() {
+    let mut req = form_request(Body::from(
+        "--X-BOUNDARY\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nabc",
+    ));
+    let mut multipart = req.body_multipart().unwrap();
+    let field = multipart.next_field().await.unwrap().unwrap();
+    match field.bytes().await {
+        Err(e) => assert!(matches!(
+            e.downcast_ref::<BodyError>(),
+            Some(BodyError::Syntax { .. })
+        )),
+        _ => panic!("truncated body was accepted"),
+    }
+}
@@ -0,0 +1,167 @@
+use hyper::header::{ACCEPT, ACCEPT_CHARSET, ACCEPT_ENCODING, ACCEPT_LANGUAGE};
+use hyper::{Body, HeaderMap, StatusCode};
+use mime::Mime;
+use std::sync::Arc;
+use tide::{Endpoint, Html, Json, Negotiated, Request};
+
+fn request(headers: &[(&str, &str)]) -> Request<()> {
+    let mut req = hyper::Request::get("/").body(Body::empty()).unwrap();
+    for &(name, value) in headers {
+        req.headers_mut().append(
+            name.parse::<hyper::header::HeaderName>().unwrap(),
+            value.parse().unwrap(),
+        );
+    }
+    Request::new(Arc::new(()), req, vec![])
+}
+
+#[test]
+fn picks_the_best_media_type() {
+    let csv: Mime = "text/csv".parse().unwrap();
+    let available = [mime::APPLICATION_JSON, mime::TEXT_HTML, csv.clone()];
+
+    let req = request(&[]);
+    assert_eq!(req.accepts(&available), Some(&mime::APPLICATION_JSON));
+
+    let req = request(&[(ACCEPT.as_str(), "text/*;q=0.5, text/csv")]);
+    assert_eq!(req.accepts(&available), Some(&csv));
+
+    let req = request(&[(ACCEPT.as_str(), "text/*, */*;q=0.1")]);
+    assert_eq!(req.accepts(&available), Some(&mime::TEXT_HTML));
+
+    let req = request(&[(ACCEPT.as_str(), "text/*, text/html;q=0")]);
+    assert_eq!(req.accepts(&available), Some(&csv));
+
+    let req = request(&[(ACCEPT.as_str(), "image/png")]);
+    assert_eq!(req.accepts(&available), None);
+}
+
+#[test]
+fn picks_the_best_encoding() {
+    let available = ["br", "gzip", "identity"];
+
+    assert_eq!(request(&[]).accepts_encoding(&available), Some("br"));
+
+    let req = request(&[(ACCEPT_ENCODING.as_str(), "gzip, br;q=0.8")]);
+    assert_eq!(req.accepts_encoding(&available), Some("gzip"));
+
+    let req = request(&[(ACCEPT_ENCODING.as_str(), "deflate")]);
+    assert_eq!(req.accepts_encoding(&available), Some("identity"));
+
+    let req = request(&[(ACCEPT_ENCODING.as_str(), "GZIP;q=0.5, *;q=0.1")]);
+    assert_eq!(req.accepts_encoding(&available), Some("gzip"));
+
+    let req = request(&[(ACCEPT_ENCODING.as_str(), "deflate, *;q=0")]);
+    assert_eq!(req.accepts_encoding(&available), None);
+}
+
+#[test]
+fn picks_the_best_language() {
+    let available = ["en-US", "fr", "de-CH"];
+
+    assert_eq!(request(&[]).accepts_language(&available), Some("en-US"));
+
+    let req = request(&[(ACCEPT_LANGUAGE.as_str(), "de, fr;q=0.8")]);
+    assert_eq!(req.accepts_language(&available), Some("de-CH"));
+
+    let req = request(&[(ACCEPT_LANGUAGE.as_str(), "en;q=0.9, en-US;q=0.2, fr;q=0.5")]);
+    assert_eq!(req.accepts_language(&available), Some("fr"));
+
+    let req = request(&[(ACCEPT_LANGUAGE.as_str(), "e, english, *;q=0.1")]);
+    assert_eq!(req.accepts_language(&available), Some("en-US"));
+
+    let req = request(&[(ACCEPT_LANGUAGE.as_str(), "ja")]);
+    assert_eq!(req.accepts_language(&available), None);
+}
+
+#[test]
+fn picks_the_best_charset() {
+    let available = ["utf-8", "iso-8859-1"];
+
+    assert_eq!(request(&[]).accepts_charset(&available), Some("utf-8"));
+
+    let req = request(&[(ACCEPT_CHARSET.as_str(), "iso-8859-1, utf-8;q=0.7")]);
+    assert_eq!(req.accepts_charset(&available), Some("iso-8859-1"));
+
+    let req = request(&[(ACCEPT_CHARSET.as_str(), "iso-8859-5")]);
+    assert_eq!(req.accepts_charset(&available), None);
+}
+
+async fn get(
+    app: &tide::server::Service<()>,
+    accept: Option<&str>,
+) -> (StatusCode, HeaderMap, String) {
+    let mut req = hyper::Request::get("/cats");
+    if let Some(accept) = accept {
+        req = req.header(ACCEPT, accept);
+    }
+    let req = Request::new(Arc::new(()), req.body(Body::empty()).unwrap(), vec![]);
+    let mut res = app.call(req).await;
+    let body = hyper::body::to_bytes(res.take_body()).await.unwrap();
+    (
+        res.status(),
+        res.headers().clone(),
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn negotiates_responses() {
+    let mut app = tide::new();
+    app.at("/cats").get(|req: Request<()>| async move {
+        let cats = vec!["nori", "chashu"];
+        Negotiated::new()
+            .json(|| Json(&cats))
+            .html(|| Html(format!("<p>{}</p>", cats.join(", "))))
+            .with("text/csv".parse().unwrap(), || {
+                format!("name\n{}\n", cats.join("\n"))
+            })
+            .respond(&req)
+    });
+    let app = app.into_http_service();
+
+    let (status, headers, body) = get(&app, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["Content-Type"], "application/json");
+    assert_eq!(body, r#"["nori","chashu"]"#);
+
+    let (status, headers, body) = get(&app, Some("text/html, */*;q=0.1")).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["Content-Type"], "text/html; charset=utf-8");
+    assert_eq!(body, "<p>nori, chashu</p>");
+
+    let (status, headers, body) = get(&app, Some("text/csv")).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["Content-Type"], "text/csv");
+    assert_eq!(body, "name\nnori\nchashu\n");
+
+    let (status, _, _) = get(&app, Some("image/png")).await;
+    assert_eq!(status, 406);
+}
+
+#[test]
+fn sets_vary() {
+    let req = request(&[(ACCEPT.as_str(), "text/html")]);
+    let res = Negotiated::new()
+        .json(|| Json(1))
+        .html(|| Html("one"))
+        .respond(&req);
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Vary"], "Accept");
+
+    let req = request(&[(ACCEPT.as_str(), "image/png")]);
+    let res = Negotiated::new().json(|| Json(1)).respond(&req);
+    assert_eq!(res.status(), 406);
+    assert_eq!(res.headers()["Vary"], "Accept");
+
+    let req = request(&[]);
+    let res = Negotiated::new()
+        .json(|| Err::<Json<u8>, _>(tide::Error::from_status(StatusCode::NOT_FOUND)))
+        .respond(&req);
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.headers()["Vary"], "Accept");
+    assert_ne!(
+        res.headers().get("Content-Type").map(|v| v.as_bytes()),
+        Some(&b"application/json"[..])
+    );
+}
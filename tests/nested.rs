@@ -2,6 +2,7 @@ use futures::future::BoxFuture;
 use hyper::{Body, body};
 use tide::{Request, Endpoint};
 use std::sync::Arc;
+use bytes::Buf;
 
 #[tokio::test]
 async fn nested() {
@@ -20,14 +21,14 @@ async fn nested() {
     let mut res = app.call(req).await;
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"foo");
+    assert_eq!(&buf[..], b"foo");
 
     let req = hyper::Request::get("/foo/bar").body(Body::empty()).unwrap();
     let req = Request::new(Arc::new(()), req, vec![]);
     let mut res = app.call(req).await;
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"bar");
+    assert_eq!(&buf[..], b"bar");
 }
 
 #[tokio::test]
@@ -64,7 +65,7 @@ async fn nested_middleware() {
     );
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"/echo");
+    assert_eq!(&buf[..], b"/echo");
 
     let req = hyper::Request::get("/foo/x/bar").body(Body::empty()).unwrap();
     let req = Request::new(Arc::new(()), req, vec![]);
@@ -75,7 +76,7 @@ async fn nested_middleware() {
     );
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"/");
+    assert_eq!(&buf[..], b"/");
 
     let req = hyper::Request::get("/bar").body(Body::empty()).unwrap();
     let req = Request::new(Arc::new(()), req, vec![]);
@@ -83,7 +84,7 @@ async fn nested_middleware() {
     assert_eq!(res.headers().get("X-Tide-Test"), None);
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"/bar");
+    assert_eq!(&buf[..], b"/bar");
 }
 
 #[tokio::test]
@@ -104,12 +105,12 @@ async fn nested_with_different_state() {
     let mut res = app.call(req).await;
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"the number is 42");
+    assert_eq!(&buf[..], b"the number is 42");
 
     let req = hyper::Request::get("/").body(Body::empty()).unwrap();
     let req = Request::new(Arc::new(()), req, vec![]);
     let mut res = app.call(req).await;
     assert_eq!(res.status(), 200);
     let buf = body::aggregate(res.take_body()).await.unwrap().to_bytes().to_vec();
-    assert_eq!(&buf[..], &*b"Hello, world!");
+    assert_eq!(&buf[..], b"Hello, world!");
 }
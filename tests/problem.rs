@@ -0,0 +1,163 @@
+use bytes::Buf;
+use hyper::{body, Body};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tide::{Endpoint, Error, IntoResponse, Problem, Request, Response, StatusCode};
+
+async fn body_json(res: &mut Response) -> Value {
+    assert_eq!(res.headers()["Content-Type"], "application/problem+json");
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn out_of_credit() -> Problem {
+    Problem::new(StatusCode::FORBIDDEN)
+        .set_type("https://example.com/probs/out-of-credit")
+        .set_title("You do not have enough credit.")
+        .set_detail("Your current balance is 30, but that costs 50.")
+        .set_instance("/account/12345/msgs/abc")
+        .set_extension("balance", 30)
+        .set_extension("accounts", vec!["/account/12345", "/account/67890"])
+}
+
+#[tokio::test]
+async fn problems_are_responses() {
+    let mut res = out_of_credit().into_response();
+    assert_eq!(res.status(), 403);
+    assert_eq!(
+        body_json(&mut res).await,
+        json!({
+            "type": "https://example.com/probs/out-of-credit",
+            "title": "You do not have enough credit.",
+            "status": 403,
+            "detail": "Your current balance is 30, but that costs 50.",
+            "instance": "/account/12345/msgs/abc",
+            "balance": 30,
+            "accounts": ["/account/12345", "/account/67890"],
+        })
+    );
+
+    let mut res = Problem::new(StatusCode::NOT_FOUND).into_response();
+    assert_eq!(
+        body_json(&mut res).await,
+        json!({"type": "about:blank", "title": "Not Found", "status": 404})
+    );
+}
+
+#[test]
+fn problems_round_trip() {
+    let problem = out_of_credit();
+    let json = serde_json::to_string(&problem).unwrap();
+    let parsed: Problem = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, problem);
+    assert_eq!(parsed.extension("balance"), Some(&json!(30)));
+
+    let parsed: Problem = serde_json::from_str(r#"{"title": "Oops"}"#).unwrap();
+    assert_eq!(parsed.problem_type(), "about:blank");
+    assert_eq!(parsed.status(), 500);
+}
+
+#[test]
+#[should_panic(expected = "`status` is a standard problem member")]
+fn extensions_cannot_replace_members() {
+    let _ = Problem::new(StatusCode::NOT_FOUND).set_extension("status", 200);
+}
+
+#[test]
+fn problems_are_errors() {
+    let err = Error::from(out_of_credit());
+    assert_eq!(err.status(), 403);
+    assert_eq!(Problem::from(&err), out_of_credit());
+    let res = err.into_response();
+    assert_eq!(res.status(), 403);
+    assert_eq!(res.headers()["Content-Type"], "application/problem+json");
+}
+
+#[derive(Deserialize)]
+struct Cat {
+    #[allow(dead_code)]
+    name: String,
+}
+
+async fn call(
+    app: &tide::server::Service<()>,
+    method: &str,
+    path: &str,
+    body: &'static str,
+) -> Response {
+    let req = hyper::Request::builder()
+        .method(method)
+        .uri(path)
+        .body(Body::from(body))
+        .unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+#[tokio::test]
+async fn servers_render_errors_as_problems() {
+    let mut app = tide::new();
+    app.problem_details(true);
+    app.at("/users/:id")
+        .get(|_| async { Err::<String, _>(Error::new(StatusCode::NOT_FOUND, "no such user")) });
+    app.at("/broken").get(|_| async {
+        std::fs::read_to_string("/does/not/exist")?;
+        Ok::<_, Error>("")
+    });
+    app.at("/credit")
+        .get(|_| async { Err::<String, Error>(out_of_credit().into()) });
+    app.at("/cats").post(|mut req: Request<()>| async move {
+        req.body_json::<Cat>().await?;
+        Ok::<_, Error>("")
+    });
+    app.at("/teapot")
+        .get(|_| async { Response::new(418).body_string("short and stout".to_string()) });
+    let app = app.into_http_service();
+
+    let mut res = call(&app, "GET", "/users/7", "").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(
+        body_json(&mut res).await,
+        json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "no such user",
+            "instance": "/users/7",
+        })
+    );
+
+    // Server errors keep their details private.
+    let mut res = call(&app, "GET", "/broken", "").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.cause().unwrap().is::<std::io::Error>());
+    let body = body_json(&mut res).await;
+    assert_eq!(body["title"], "Internal Server Error");
+    assert_eq!(body.get("detail"), None);
+
+    let mut res = call(&app, "GET", "/credit", "").await;
+    assert_eq!(res.status(), 403);
+    assert_eq!(body_json(&mut res).await["balance"], 30);
+
+    let mut res = call(&app, "POST", "/cats", "{}").await;
+    assert_eq!(res.status(), 422);
+    let body = body_json(&mut res).await;
+    assert!(body["detail"]
+        .as_str()
+        .unwrap()
+        .starts_with("missing field `name`"));
+    assert_eq!(body["error"], "invalid_data");
+
+    let mut res = call(&app, "GET", "/nowhere", "").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(body_json(&mut res).await["instance"], "/nowhere");
+
+    let mut res = call(&app, "DELETE", "/broken", "").await;
+    assert_eq!(res.status(), 405);
+    assert_eq!(body_json(&mut res).await["title"], "Method Not Allowed");
+
+    // Responses describing the error in their own way are left alone.
+    let res = call(&app, "GET", "/teapot", "").await;
+    assert_eq!(res.status(), 418);
+    assert!(res.headers().get("Content-Type").unwrap() != "application/problem+json");
+}
@@ -0,0 +1,66 @@
+use bytes::Buf;
+use hyper::{body, Body};
+use std::sync::Arc;
+use tide::{Endpoint, Request, Response};
+
+async fn get(app: &tide::server::Service<()>, path: &str) -> (u16, String) {
+    let req = hyper::Request::get(path).body(Body::empty()).unwrap();
+    let req = Request::new(Arc::new(()), req, vec![]);
+    let mut res: Response = app.call(req).await;
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    (res.status().as_u16(), String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn swap_routes() {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { "old" });
+    app.at("/legacy").get(|_| async { "legacy" });
+    let handle = app.reload_handle();
+    let app = app.into_http_service();
+
+    assert_eq!(get(&app, "/").await, (200, "old".to_string()));
+    assert_eq!(get(&app, "/legacy").await, (200, "legacy".to_string()));
+
+    let mut next = tide::new();
+    next.at("/").get(|_| async { "new" });
+    next.at("/admin").get(|_| async { "admin" });
+    handle.swap(next);
+
+    assert_eq!(get(&app, "/").await, (200, "new".to_string()));
+    assert_eq!(get(&app, "/admin").await, (200, "admin".to_string()));
+    assert_eq!(get(&app, "/legacy").await.0, 404);
+}
+
+#[tokio::test]
+async fn routes_swapped_before_serving_are_kept() {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { "old" });
+    let handle = app.reload_handle();
+
+    let mut next = tide::new();
+    next.at("/").get(|_| async { "new" });
+    handle.swap(next);
+
+    let app = app.into_http_service();
+    assert_eq!(get(&app, "/").await, (200, "new".to_string()));
+}
+
+#[tokio::test]
+async fn in_flight_requests_keep_their_routes() {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { "old" });
+    let app = app.into_http_service();
+
+    let req = hyper::Request::get("/").body(Body::empty()).unwrap();
+    let in_flight = app.call(Request::new(Arc::new(()), req, vec![]));
+
+    let mut next = tide::new();
+    next.at("/").get(|_| async { "new" });
+    app.reload_handle().swap(next);
+
+    let mut res = in_flight.await;
+    let body = body::aggregate(res.take_body()).await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"old");
+    assert_eq!(get(&app, "/").await, (200, "new".to_string()));
+}
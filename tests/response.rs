@@ -1,54 +1,181 @@
-// use futures::executor::block_on;
-// use tide::*;
-
-// #[test]
-// fn test_status() {
-//     let resp = "foo"
-//         .with_status(http::status::StatusCode::NOT_FOUND)
-//         .into_response();
-//     assert_eq!(resp.status(), http::status::StatusCode::NOT_FOUND);
-//     assert_eq!(block_on(resp.into_body().into_vec()).unwrap(), b"foo");
-// }
-
-// #[test]
-// fn byte_vec_content_type() {
-//     let resp = String::from("foo").into_bytes().into_response();
-//     assert_eq!(resp.headers()["Content-Type"], "application/octet-stream");
-//     assert_eq!(block_on(resp.into_body().into_vec()).unwrap(), b"foo");
-// }
-
-// #[test]
-// fn string_content_type() {
-//     let resp = String::from("foo").into_response();
-//     assert_eq!(resp.headers()["Content-Type"], "text/plain; charset=utf-8");
-//     assert_eq!(block_on(resp.into_body().into_vec()).unwrap(), b"foo");
-// }
-
-// #[test]
-// fn json_content_type() {
-//     use std::collections::BTreeMap;
-
-//     let mut map = BTreeMap::new();
-//     map.insert(Some("a"), 2);
-//     map.insert(Some("b"), 4);
-//     map.insert(None, 6);
-
-//     let resp = json(map);
-//     assert_eq!(
-//         resp.status(),
-//         http::status::StatusCode::INTERNAL_SERVER_ERROR
-//     );
-//     assert_eq!(block_on(resp.into_body().into_vec()).unwrap(), b"");
-
-//     let mut map = BTreeMap::new();
-//     map.insert("a", 2);
-//     map.insert("b", 4);
-//     map.insert("c", 6);
-
-//     let resp = json(map);
-//     assert_eq!(resp.status(), http::status::StatusCode::OK);
-//     assert_eq!(
-//         block_on(resp.into_body().into_vec()).unwrap(),
-//         br##"{"a":2,"b":4,"c":6}"##
-//     );
-// }
+use hyper::body;
+use tide::{Error, IntoResponse, Response, StatusCode};
+
+mod common;
+
+use common::body_string;
+
+#[tokio::test]
+async fn results_respond_with_either_side() {
+    let mut res = Ok::<_, Error>("hello").into_response();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, "hello");
+
+    let res = Err::<&str, _>(Error::new(StatusCode::CONFLICT, "taken")).into_response();
+    assert_eq!(res.status(), 409);
+}
+
+#[tokio::test]
+async fn success_coded_errors_become_server_errors() {
+    let mut res = Err::<&str, _>("oops").into_response();
+    assert_eq!(res.status(), 500);
+    assert_eq!(body_string(&mut res).await, "");
+
+    let mut err = Error::new(StatusCode::BAD_REQUEST, "not really an error");
+    err.set_status(StatusCode::OK);
+    let res = Err::<&str, _>(err).into_response();
+    assert_eq!(res.status(), 500);
+    assert_eq!(res.cause().unwrap().to_string(), "not really an error");
+}
+
+#[tokio::test]
+async fn options_respond_with_not_found() {
+    let mut res = Some("found").into_response();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, "found");
+
+    let res = None::<String>.into_response();
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.cause().map(|e| e.to_string()), None);
+}
+
+#[tokio::test]
+async fn typed_bodies_set_their_content_type() {
+    use serde::Serialize;
+    use tide::{Bytes, Form, Html, Json};
+
+    #[derive(Serialize)]
+    struct Cat {
+        name: &'static str,
+        lives: u8,
+    }
+
+    let cases = vec![
+        (
+            Json(Cat {
+                name: "nori",
+                lives: 9,
+            })
+            .into_response(),
+            "application/json",
+            r#"{"name":"nori","lives":9}"#,
+        ),
+        (
+            serde_json::json!({"ok": true}).into_response(),
+            "application/json",
+            r#"{"ok":true}"#,
+        ),
+        (
+            Form(Cat {
+                name: "nori",
+                lives: 9,
+            })
+            .into_response(),
+            "application/x-www-form-urlencoded",
+            "name=nori&lives=9",
+        ),
+        (
+            Html("<p>hi</p>").into_response(),
+            "text/html; charset=utf-8",
+            "<p>hi</p>",
+        ),
+        (
+            Bytes(&b"GIF89a"[..]).into_response(),
+            "application/octet-stream",
+            "GIF89a",
+        ),
+        (
+            b"raw".to_vec().into_response(),
+            "application/octet-stream",
+            "raw",
+        ),
+    ];
+    for (mut res, content_type, body) in cases {
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["Content-Type"], content_type);
+        assert_eq!(body_string(&mut res).await, body);
+    }
+}
+
+#[tokio::test]
+async fn unserializable_bodies_are_server_errors() {
+    use std::collections::BTreeMap;
+    use tide::Json;
+
+    let mut map = BTreeMap::new();
+    map.insert(Some("a"), 2);
+    map.insert(None, 6);
+    let mut res = Json(map).into_response();
+    assert_eq!(res.status(), 500);
+    assert!(res.cause().is_some());
+    assert_eq!(body_string(&mut res).await, "");
+}
+
+#[tokio::test]
+async fn statuses_and_headers_respond_as_given() {
+    use hyper::HeaderMap;
+
+    let mut res = ().into_response();
+    assert_eq!(res.status(), 204);
+    assert_eq!(body_string(&mut res).await, "");
+
+    let res = StatusCode::ACCEPTED.into_response();
+    assert_eq!(res.status(), 202);
+
+    let mut res = (StatusCode::CREATED, "made").into_response();
+    assert_eq!(res.status(), 201);
+    assert_eq!(body_string(&mut res).await, "made");
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Location", "/cats/1".parse().unwrap());
+    headers.insert("Content-Type", "text/csv".parse().unwrap());
+    let mut res = (StatusCode::CREATED, headers, "name\nnori").into_response();
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["Location"], "/cats/1");
+    assert_eq!(res.headers()["Content-Type"], "text/csv");
+    assert_eq!(res.headers().get_all("Content-Type").iter().count(), 1);
+    assert_eq!(body_string(&mut res).await, "name\nnori");
+}
+
+#[tokio::test]
+async fn streams_bodies() {
+    use futures::stream;
+
+    let chunks = vec![Ok::<_, std::io::Error>("hello, "), Ok("world")];
+    let mut res = Response::from_stream(200, stream::iter(chunks), Some(12));
+    assert_eq!(res.headers()["Content-Type"], "application/octet-stream");
+    assert_eq!(res.headers()["Content-Length"], "12");
+    assert_eq!(body_string(&mut res).await, "hello, world");
+
+    let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+    let reader = std::io::Cursor::new(data.clone());
+    let mut res = Response::from_async_read(200, reader, None);
+    assert_eq!(res.headers().get("Content-Length"), None);
+    let body = body::to_bytes(res.take_body()).await.unwrap();
+    assert_eq!(body.as_ref(), &data[..]);
+
+    let lines = stream::iter(vec![serde_json::json!({"id": 1}), serde_json::json!([2])]);
+    let mut res = Response::ndjson(200, lines);
+    assert_eq!(res.headers()["Content-Type"], "application/x-ndjson");
+    assert_eq!(body_string(&mut res).await, "{\"id\":1}\n[2]\n");
+}
+
+#[tokio::test]
+async fn streams_are_pulled_on_demand() {
+    use futures::{stream, StreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let pulled = Arc::new(AtomicUsize::new(0));
+    let counter = pulled.clone();
+    let chunks = stream::iter(0..100).map(move |i| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok::<_, std::io::Error>(vec![i as u8; 1024])
+    });
+    let mut res = Response::from_stream(200, chunks, None);
+    assert_eq!(pulled.load(Ordering::SeqCst), 0);
+
+    let mut body = res.take_body();
+    assert_eq!(body.next().await.unwrap().unwrap().len(), 1024);
+    assert_eq!(pulled.load(Ordering::SeqCst), 1);
+}
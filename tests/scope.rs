@@ -0,0 +1,85 @@
+use futures::future::BoxFuture;
+use hyper::Body;
+use std::sync::Arc;
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::body_string;
+
+#[derive(Clone)]
+struct Role(&'static str);
+
+fn tag(req: Request<()>, next: tide::Next<'_, ()>) -> BoxFuture<'_, Response> {
+    Box::pin(async move {
+        let role = req.local::<Role>().map(|role| role.0).unwrap_or("none");
+        next.run(req).await.set_header("X-Role", role)
+    })
+}
+
+fn app() -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.at("/users").get(|_| async { "top-level users" });
+    app.scope("/api/v1", |api| {
+        api.metadata(Role("user"));
+        api.middleware(tag);
+        api.at("/users").get(|_| async { "v1 users" });
+        api.scope("/admin", |admin| {
+            admin.metadata(Role("admin"));
+            admin.at("/stats").get(|_| async { "stats" });
+        });
+        api.fallback(|req: Request<()>| async move {
+            Response::new(404).body_string(format!("no v1 route for {}", req.uri().path()))
+        });
+    });
+    app.into_http_service()
+}
+
+async fn get(path: &str) -> Response {
+    let req = hyper::Request::get(path).body(Body::empty()).unwrap();
+    let req = Request::new(Arc::new(()), req, vec![]);
+    app().call(req).await
+}
+
+#[tokio::test]
+async fn scoped_routes_and_middleware() {
+    let mut res = get("/api/v1/users").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["X-Role"], "user");
+    assert_eq!(body_string(&mut res).await, "v1 users");
+
+    let mut res = get("/users").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get("X-Role"), None);
+    assert_eq!(body_string(&mut res).await, "top-level users");
+}
+
+#[tokio::test]
+async fn nested_scopes_inherit_middleware_and_override_metadata() {
+    let mut res = get("/api/v1/admin/stats").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["X-Role"], "admin");
+    assert_eq!(body_string(&mut res).await, "stats");
+}
+
+#[tokio::test]
+async fn scoped_fallback() {
+    let mut res = get("/api/v1/missing/path").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.headers()["X-Role"], "user");
+    assert_eq!(
+        body_string(&mut res).await,
+        "no v1 route for /api/v1/missing/path"
+    );
+
+    let res = get("/missing").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.headers().get("X-Role"), None);
+
+    let req = hyper::Request::post("/api/v1/users")
+        .body(Body::empty())
+        .unwrap();
+    let req = Request::new(Arc::new(()), req, vec![]);
+    let res = app().call(req).await;
+    assert_eq!(res.status(), 405);
+}
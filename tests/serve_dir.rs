@@ -0,0 +1,360 @@
+use hyper::Body;
+use std::sync::Arc;
+use tide::fs::{ServeDir, ServeFile};
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::{body_bytes, body_string};
+
+const ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/static");
+
+fn app() -> tide::server::Service<()> {
+    let mut app = tide::new();
+    app.at("/static").serve_dir(ROOT);
+    app.at("/plain")
+        .serve_dir(ServeDir::new(ROOT).index_files(vec!["readme.txt"]));
+    app.at("/assets")
+        .serve_dir(ServeDir::new(ROOT).precompressed(true));
+    app.at("/listing").serve_dir(
+        ServeDir::new(ROOT)
+            .index_files(Vec::<String>::new())
+            .listing(true),
+    );
+    app.at("/hidden")
+        .serve_dir(ServeDir::new(ROOT).listing(true).show_hidden(true));
+    app.at("/download")
+        .get(ServeFile::new(format!("{}/docs/digits.txt", ROOT)));
+    app.into_http_service()
+}
+
+async fn get(path: &str) -> Response {
+    get_with(path, &[]).await
+}
+
+async fn get_with(path: &str, headers: &[(&str, &str)]) -> Response {
+    let mut req = hyper::Request::get(path);
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let req = Request::new(Arc::new(()), req.body(Body::empty()).unwrap(), vec![]);
+    app().call(req).await
+}
+
+#[tokio::test]
+async fn serves_files_with_mime_and_length() {
+    let mut res = get("/static/css/style.css").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/css");
+    assert_eq!(res.headers()["Content-Length"], "21");
+    assert_eq!(body_string(&mut res).await, "body { color: red; }\n");
+
+    let res = get("/static/docs/read%6de.txt").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/plain");
+}
+
+#[tokio::test]
+async fn serves_index_files() {
+    let mut res = get("/static/").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/html");
+    assert!(body_string(&mut res).await.contains("<title>index</title>"));
+
+    let res = get("/static").await;
+    assert_eq!(res.status(), 301);
+    assert_eq!(res.headers()["Location"], "/static/");
+
+    let res = get("/static/docs?x=1").await;
+    assert_eq!(res.status(), 301);
+    assert_eq!(res.headers()["Location"], "/static/docs/?x=1");
+
+    let res = get("/static/docs/").await;
+    assert_eq!(res.status(), 404);
+
+    let mut res = get("/plain/docs/").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_string(&mut res).await, "read me\n");
+}
+
+#[tokio::test]
+async fn missing_files() {
+    assert_eq!(get("/static/missing.css").await.status(), 404);
+    assert_eq!(get("/static/index.html/nested").await.status(), 404);
+}
+
+#[tokio::test]
+async fn rejects_path_traversal() {
+    assert_eq!(get("/static/../Cargo.toml").await.status(), 403);
+    assert_eq!(get("/static/css/../../../Cargo.toml").await.status(), 403);
+    assert_eq!(get("/static/%2e%2e/%2e%2e/Cargo.toml").await.status(), 403);
+    assert_eq!(get("/static/..%2f..%2fCargo.toml").await.status(), 403);
+    assert_eq!(
+        get("/static/css%5c..%5c..%5cCargo.toml").await.status(),
+        403
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn rejects_symlinks_leaving_the_directory() {
+    let dir = std::env::temp_dir().join(format!("tide-serve-dir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let link = dir.join("escape.toml");
+    let _ = std::fs::remove_file(&link);
+    std::os::unix::fs::symlink(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"), &link).unwrap();
+
+    let mut app = tide::new();
+    app.at("/tmp").serve_dir(dir.clone());
+    let app = app.into_http_service();
+    let req = hyper::Request::get("/tmp/escape.toml")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.call(Request::new(Arc::new(()), req, vec![])).await;
+    assert_eq!(res.status(), 403);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn single_ranges() {
+    let mut res = get_with("/static/docs/digits.txt", &[("Range", "bytes=2-5")]).await;
+    assert_eq!(res.status(), 206);
+    assert_eq!(res.headers()["Content-Range"], "bytes 2-5/20");
+    assert_eq!(res.headers()["Content-Length"], "4");
+    assert_eq!(res.headers()["Content-Type"], "text/plain");
+    assert_eq!(body_string(&mut res).await, "2345");
+
+    let mut res = get_with("/download", &[("Range", "bytes=-3")]).await;
+    assert_eq!(res.status(), 206);
+    assert_eq!(res.headers()["Content-Range"], "bytes 17-19/20");
+    assert_eq!(body_string(&mut res).await, "hij");
+
+    let res = get_with("/download", &[("Range", "bytes=20-")]).await;
+    assert_eq!(res.status(), 416);
+    assert_eq!(res.headers()["Content-Range"], "bytes */20");
+
+    let mut res = get_with("/download", &[("Range", "bytes=5-2")]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Accept-Ranges"], "bytes");
+    assert_eq!(body_string(&mut res).await, "0123456789abcdefghij");
+}
+
+#[tokio::test]
+async fn multiple_ranges() {
+    let mut res = get_with("/download", &[("Range", "bytes=0-1,10-")]).await;
+    assert_eq!(res.status(), 206);
+    let content_type = res.headers()["Content-Type"].to_str().unwrap().to_string();
+    let boundary = content_type
+        .strip_prefix("multipart/byteranges; boundary=")
+        .unwrap();
+    let expected = format!(
+        "\r\n--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/20\r\n\r\n01\
+         \r\n--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 10-19/20\r\n\r\nabcdefghij\
+         \r\n--{b}--\r\n",
+        b = boundary
+    );
+    assert_eq!(res.headers()["Content-Length"], expected.len().to_string());
+    assert_eq!(body_string(&mut res).await, expected);
+}
+
+#[tokio::test]
+async fn overlapping_ranges() {
+    let mut res = get_with("/download", &[("Range", "bytes=4-5,0-1,2-3")]).await;
+    assert_eq!(res.status(), 206);
+    assert_eq!(res.headers()["Content-Range"], "bytes 0-5/20");
+    assert_eq!(body_string(&mut res).await, "012345");
+
+    let repeated = vec!["0-"; 32].join(",");
+    let range = format!("bytes={}", repeated);
+    let mut res = get_with("/download", &[("Range", &range)]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Length"], "20");
+    assert_eq!(body_string(&mut res).await, "0123456789abcdefghij");
+}
+
+#[tokio::test]
+async fn conditional_requests() {
+    let res = get("/download").await;
+    let etag = res.headers()["ETag"].to_str().unwrap().to_string();
+    let last_modified = res.headers()["Last-Modified"].to_str().unwrap().to_string();
+    assert!(etag.starts_with('"'));
+
+    let res = get_with("/download", &[("If-None-Match", &etag)]).await;
+    assert_eq!(res.status(), 304);
+    assert_eq!(res.headers()["ETag"], etag.as_str());
+
+    let weak = format!("\"other\", W/{}", etag);
+    assert_eq!(
+        get_with("/download", &[("If-None-Match", &weak)])
+            .await
+            .status(),
+        304
+    );
+    let res = get_with("/download", &[("If-None-Match", "\"other\"")]).await;
+    assert_eq!(res.status(), 200);
+
+    let res = get_with("/download", &[("If-Modified-Since", &last_modified)]).await;
+    assert_eq!(res.status(), 304);
+    let since = "Thu, 01 Jan 1970 00:00:00 GMT";
+    assert_eq!(
+        get_with("/download", &[("If-Modified-Since", since)])
+            .await
+            .status(),
+        200
+    );
+
+    // If-None-Match takes precedence over If-Modified-Since.
+    let headers = [
+        ("If-None-Match", "\"other\""),
+        ("If-Modified-Since", last_modified.as_str()),
+    ];
+    assert_eq!(get_with("/download", &headers).await.status(), 200);
+}
+
+#[tokio::test]
+async fn if_range() {
+    let res = get("/download").await;
+    let etag = res.headers()["ETag"].to_str().unwrap().to_string();
+    let last_modified = res.headers()["Last-Modified"].to_str().unwrap().to_string();
+
+    let res = get_with("/download", &[("Range", "bytes=0-1"), ("If-Range", &etag)]).await;
+    assert_eq!(res.status(), 206);
+    let res = get_with(
+        "/download",
+        &[("Range", "bytes=0-1"), ("If-Range", &last_modified)],
+    )
+    .await;
+    assert_eq!(res.status(), 206);
+
+    let res = get_with(
+        "/download",
+        &[("Range", "bytes=0-1"), ("If-Range", "\"old\"")],
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+    let weak = format!("W/{}", etag);
+    let res = get_with("/download", &[("Range", "bytes=0-1"), ("If-Range", &weak)]).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn precompressed_files() {
+    let fixture = |name: &str| std::fs::read(format!("{}/js/{}", ROOT, name)).unwrap();
+
+    let mut res = get_with("/assets/js/app.js", &[("Accept-Encoding", "gzip, br")]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/javascript");
+    assert_eq!(res.headers()["Content-Encoding"], "br");
+    assert_eq!(res.headers()["Vary"], "Accept-Encoding");
+    assert_eq!(body_bytes(&mut res).await, fixture("app.js.br"));
+    let br_etag = res.headers()["ETag"].clone();
+
+    let mut res = get_with(
+        "/assets/js/app.js",
+        &[("Accept-Encoding", "gzip;q=1, br;q=0.5")],
+    )
+    .await;
+    assert_eq!(res.headers()["Content-Type"], "text/javascript");
+    assert_eq!(res.headers()["Content-Encoding"], "gzip");
+    assert_eq!(body_bytes(&mut res).await, fixture("app.js.gz"));
+    assert_ne!(res.headers()["ETag"], br_etag);
+
+    let mut res = get("/assets/js/app.js").await;
+    assert!(res.headers().get("Content-Encoding").is_none());
+    assert_eq!(res.headers()["Vary"], "Accept-Encoding");
+    assert_eq!(body_bytes(&mut res).await, fixture("app.js"));
+
+    let res = get_with(
+        "/assets/js/app.js",
+        &[("If-None-Match", br_etag.to_str().unwrap())],
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+    let res = get_with(
+        "/assets/js/app.js",
+        &[
+            ("Accept-Encoding", "br"),
+            ("If-None-Match", br_etag.to_str().unwrap()),
+        ],
+    )
+    .await;
+    assert_eq!(res.status(), 304);
+    assert_eq!(res.headers()["Vary"], "Accept-Encoding");
+
+    // Precompressed files are only used when asked for.
+    let res = get_with("/static/js/app.js", &[("Accept-Encoding", "br")]).await;
+    assert!(res.headers().get("Content-Encoding").is_none());
+    assert!(res.headers().get("Vary").is_none());
+}
+
+#[tokio::test]
+async fn json_listing() {
+    let json = "application/json";
+    let listing = |mut res: Response| async move {
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["Content-Type"], "application/json");
+        assert_eq!(res.headers()["Vary"], "Accept");
+        serde_json::from_str::<serde_json::Value>(&body_string(&mut res).await).unwrap()
+    };
+    let names = |listing: &serde_json::Value| {
+        listing["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let docs = listing(get_with("/listing/docs/", &[("Accept", json)]).await).await;
+    assert_eq!(docs["path"], "/listing/docs/");
+    assert_eq!(names(&docs), vec!["digits.txt", "readme.txt"]);
+    let digits = &docs["entries"][0];
+    assert_eq!(digits["type"], "file");
+    assert_eq!(digits["size"], 20);
+    assert!(digits["modified"].as_u64().unwrap() > 0);
+
+    let root = listing(get_with("/listing/", &[("Accept", json)]).await).await;
+    let css = root["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["name"] == "css")
+        .unwrap();
+    assert_eq!(css["type"], "directory");
+    assert!(css["size"].is_null());
+
+    let path = "/listing/docs/?sort=size";
+    let by_size = listing(get_with(path, &[("Accept", json)]).await).await;
+    assert_eq!(names(&by_size), vec!["readme.txt", "digits.txt"]);
+    let path = "/listing/docs/?sort=name&order=desc";
+    let desc = listing(get_with(path, &[("Accept", json)]).await).await;
+    assert_eq!(names(&desc), vec!["readme.txt", "digits.txt"]);
+
+    let hidden = listing(get_with("/hidden/docs/", &[("Accept", json)]).await).await;
+    assert_eq!(names(&hidden), vec![".hidden", "digits.txt", "readme.txt"]);
+}
+
+#[tokio::test]
+async fn html_listing() {
+    let accept = "text/html,application/xhtml+xml,application/json;q=0.9,*/*;q=0.8";
+    let mut res = get_with("/listing/", &[("Accept", accept)]).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Content-Type"], "text/html; charset=utf-8");
+    let html = body_string(&mut res).await;
+    assert!(html.contains("<title>Index of /listing/</title>"));
+    assert!(html.contains("<a href=\"css/\">css/</a>"));
+    assert!(html.contains("<a href=\"index.html\">index.html</a>"));
+    assert!(!html.contains("../"));
+
+    let mut res = get("/listing/docs/?sort=name").await;
+    let html = body_string(&mut res).await;
+    assert!(html.contains("<a href=\"../\">../</a>"));
+    assert!(html.contains("<a href=\"?sort=name&amp;order=desc\">Name</a>"));
+    assert!(html.contains("<td>20</td>"));
+    assert!(!html.contains(".hidden"));
+
+    // Index files take precedence over listings.
+    let mut res = get("/hidden/").await;
+    assert!(body_string(&mut res).await.contains("<title>index</title>"));
+}
@@ -0,0 +1,102 @@
+use hyper::Body;
+use std::sync::Arc;
+use tide::fs::ServeDir;
+use tide::{Endpoint, Request, Response};
+
+mod common;
+
+use common::body_string;
+
+const ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/spa");
+const HTML: &str = "text/html,application/xhtml+xml,*/*;q=0.8";
+
+fn spa() -> ServeDir {
+    ServeDir::new(ROOT)
+        .spa_fallback("index.html")
+        .spa_exclude("/api/")
+}
+
+async fn request(method: &str, path: &str, accept: &str) -> Response {
+    request_from(spa(), method, path, accept).await
+}
+
+async fn request_from(dir: ServeDir, method: &str, path: &str, accept: &str) -> Response {
+    let mut app = tide::new();
+    app.at("/api/users").get(|_| async { "[]" });
+    app.at("/").serve_dir(dir);
+    let app = app.into_http_service();
+
+    let req = hyper::Request::builder()
+        .method(method)
+        .uri(path)
+        .header("Accept", accept)
+        .body(Body::empty())
+        .unwrap();
+    app.call(Request::new(Arc::new(()), req, vec![])).await
+}
+
+#[tokio::test]
+async fn unmatched_pages_serve_the_index() {
+    for path in &[
+        "/",
+        "/index.html",
+        "/users/42",
+        "/settings/",
+        "/assets/unknown",
+    ] {
+        let mut res = request("GET", path, HTML).await;
+        assert_eq!(res.status(), 200, "{}", path);
+        assert_eq!(res.headers()["Content-Type"], "text/html");
+        assert_eq!(res.headers()["Cache-Control"], "no-cache");
+        assert!(body_string(&mut res).await.contains("<title>spa</title>"));
+    }
+
+    let res = request("HEAD", "/users/42", HTML).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn other_requests_are_not_found() {
+    // Missing assets.
+    assert_eq!(request("GET", "/assets/logo.png", HTML).await.status(), 404);
+    assert_eq!(request("GET", "/users/me.json", HTML).await.status(), 404);
+    // Clients not asking for HTML.
+    let status = request("GET", "/users/42", "application/json")
+        .await
+        .status();
+    assert_eq!(status, 404);
+    // Excluded prefixes.
+    assert_eq!(request("GET", "/api", HTML).await.status(), 404);
+    assert_eq!(request("GET", "/api/posts", HTML).await.status(), 404);
+    assert_eq!(request("GET", "/apis", HTML).await.status(), 200);
+    // Other methods.
+    assert_eq!(request("POST", "/users/42", HTML).await.status(), 405);
+
+    let mut res = request("GET", "/api/users", "application/json").await;
+    assert_eq!(body_string(&mut res).await, "[]");
+}
+
+#[tokio::test]
+async fn cache_control() {
+    let immutable = || spa().immutable_assets(true);
+    let res = request_from(immutable(), "GET", "/assets/main.3f2a1b9c.js", "*/*").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.headers()["Cache-Control"],
+        "public, max-age=31536000, immutable"
+    );
+
+    for path in &[
+        "/robots.txt",
+        "/assets/backup-20201231.zip",
+        "/assets/report.v20201231.csv",
+    ] {
+        let res = request_from(immutable(), "GET", path, "*/*").await;
+        assert_eq!(res.status(), 200, "{}", path);
+        assert!(res.headers().get("Cache-Control").is_none(), "{}", path);
+    }
+
+    let res = request("GET", "/assets/main.3f2a1b9c.js", "*/*").await;
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().get("Cache-Control").is_none());
+}
@@ -0,0 +1,82 @@
+use headers::authorization::{Authorization, Bearer};
+use headers::{ETag, IfNoneMatch, Range};
+use hyper::Body;
+use std::sync::Arc;
+use tide::{Endpoint, Error, Request, Response};
+
+async fn get(app: &tide::server::Service<()>, headers: &[(&str, &str)]) -> (u16, String) {
+    let mut req = hyper::Request::get("/");
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let req = req.body(Body::empty()).unwrap();
+    let mut res: Response = app.call(Request::new(Arc::new(()), req, vec![])).await;
+    let body = hyper::body::to_bytes(res.take_body()).await.unwrap();
+    (
+        res.status().as_u16(),
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn reads_typed_headers() {
+    let mut app = tide::new();
+    app.at("/").get(|req: Request<()>| async move {
+        let token = req
+            .typed_header::<Authorization<Bearer>>()?
+            .map(|auth| auth.0.token().to_string());
+        let range = req.typed_header::<Range>()?.map(|range| {
+            range
+                .iter()
+                .map(|bounds| format!("{:?}", bounds))
+                .collect::<Vec<_>>()
+        });
+        Ok::<_, Error>(format!("{:?} {:?}", token, range))
+    });
+    let app = app.into_http_service();
+
+    assert_eq!(get(&app, &[]).await, (200, "None None".to_string()));
+    assert_eq!(
+        get(
+            &app,
+            &[("Authorization", "Bearer t0k3n"), ("Range", "bytes=0-9")]
+        )
+        .await,
+        (
+            200,
+            r#"Some("t0k3n") Some(["(Included(0), Included(9))"])"#.to_string()
+        )
+    );
+    assert_eq!(
+        get(&app, &[("Range", "lines=0-9")]).await,
+        (400, "invalid range header".to_string())
+    );
+    assert_eq!(
+        get(&app, &[("Authorization", "Basic !!")]).await,
+        (400, "invalid authorization header".to_string())
+    );
+}
+
+#[tokio::test]
+async fn writes_typed_headers() {
+    let mut app = tide::new();
+    app.at("/").get(|req: Request<()>| async move {
+        let etag: ETag = "\"v1\"".parse().unwrap();
+        let unchanged = req
+            .typed_header::<IfNoneMatch>()?
+            .is_some_and(|tags| !tags.precondition_passes(&etag));
+        let res = if unchanged {
+            Response::new(304)
+        } else {
+            Response::new(200).body_string("v1".to_string())
+        };
+        Ok::<_, Error>(res.set_typed_header(etag))
+    });
+    let app = app.into_http_service();
+
+    assert_eq!(get(&app, &[]).await, (200, "v1".to_string()));
+    assert_eq!(
+        get(&app, &[("If-None-Match", "\"v0\", \"v1\"")]).await,
+        (304, "".to_string())
+    );
+}